@@ -1,7 +1,7 @@
-use super::AnnotationType;
-
-#[derive(Debug)]
-pub struct AnnotatedStringPart<'a> {
-    pub string: &'a str,
-    pub annotation_type: Option<AnnotationType>,
-}
+use super::AnnotationType;
+
+#[derive(Debug)]
+pub struct AnnotatedStringPart<'a> {
+    pub string: &'a str,
+    pub annotation_type: Option<AnnotationType>,
+}
@@ -1,13 +1,18 @@
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum AnnotationType {
-    Match,
-    SelectedMatch,
-    Number,
-    Keyword,
-    Type,
-    KnownValue,
-    Char,
-    LifeTimeSpecifier,
-    Comment,
-    String,
-}
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnnotationType {
+    Match,
+    SelectedMatch,
+    Selection,
+    Number,
+    Keyword,
+    Type,
+    KnownValue,
+    Char,
+    LifeTimeSpecifier,
+    Comment,
+    String,
+    LineNumber,
+    CurrentLineNumber,
+    MatchingBracket,
+    Tab,
+}
@@ -0,0 +1,19 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Clipboard {
+    Cut,
+    Copy,
+    Paste,
+}
+
+impl TryFrom<&str> for Clipboard {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "cut" => Ok(Self::Cut),
+            "copy" => Ok(Self::Copy),
+            "paste" => Ok(Self::Paste),
+            _ => Err(format!("Unknown clipboard command: {name}")),
+        }
+    }
+}
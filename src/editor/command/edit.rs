@@ -1,36 +1,30 @@
-use crossterm::event::{
-    KeyCode::{Backspace, Char, Delete, Enter, Tab},
-    KeyEvent, KeyModifiers,
-};
-
-#[derive(Clone, Copy)]
-pub enum Edit {
-    Insert(char),
-    InsertNewline,
-    Delete,
-    DeleteBackward,
-}
-
-impl TryFrom<KeyEvent> for Edit {
-    type Error = String;
-
-    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-        let KeyEvent {
-            code, modifiers, ..
-        } = event;
-
-        match (code, modifiers) {
-            (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                Ok(Self::Insert(character))
-            }
-            (Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
-            (Enter, KeyModifiers::NONE) => Ok(Self::InsertNewline),
-            (Backspace, KeyModifiers::NONE) => Ok(Self::DeleteBackward),
-            (Delete, KeyModifiers::NONE) => Ok(Self::Delete),
-            _ => Err(format!(
-                "Unknown key code {:?} with modifiers {:?}",
-                code, modifiers
-            )),
-        }
-    }
-}
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Insert(char),
+    InsertNewline,
+    Delete,
+    DeleteBackward,
+    DeleteWord,
+    DeleteWordBackward,
+    Undo,
+    Redo,
+}
+
+impl TryFrom<&str> for Edit {
+    type Error = String;
+
+    // Typing a character isn't bound to a key, the keymap falls back to it for keys it doesn't know
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "insert_tab" => Ok(Self::Insert('\t')),
+            "insert_newline" => Ok(Self::InsertNewline),
+            "delete" => Ok(Self::Delete),
+            "delete_backward" => Ok(Self::DeleteBackward),
+            "delete_word" => Ok(Self::DeleteWord),
+            "delete_word_backward" => Ok(Self::DeleteWordBackward),
+            "undo" => Ok(Self::Undo),
+            "redo" => Ok(Self::Redo),
+            _ => Err(format!("Unknown edit command: {name}")),
+        }
+    }
+}
@@ -1,37 +1,61 @@
-use crate::prelude::*;
-use crossterm::event::Event;
-pub use edit::Edit;
-pub use movecommand::Move;
-use std::convert::TryFrom;
-pub use system::System;
-
-mod edit;
-mod movecommand;
-mod system;
-
-#[derive(Clone, Copy)]
-pub enum Command {
-    Move(Move),
-    Edit(Edit),
-    System(System),
-}
-
-#[allow(clippy::as_conversions)]
-impl TryFrom<Event> for Command {
-    type Error = String;
-
-    fn try_from(event: Event) -> Result<Self, Self::Error> {
-        match event {
-            Event::Key(key_event) => Edit::try_from(key_event)
-                .map(Command::Edit)
-                .or_else(|_| Move::try_from(key_event).map(Command::Move))
-                .or_else(|_| System::try_from(key_event).map(Command::System))
-                .map_err(|_err| format!("Event not supported: {key_event:?}")),
-            Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
-                width: width_u16 as usize,
-                height: height_u16 as usize,
-            }))),
-            _ => Err(format!("Event not supported: {event:?}")),
-        }
-    }
-}
+use crate::prelude::*;
+pub use clipboard::Clipboard;
+use crossterm::event::Event;
+pub use edit::Edit;
+pub use movecommand::{Move, SelectionMode};
+pub use pane::Pane;
+use std::convert::TryFrom;
+pub use system::System;
+
+mod clipboard;
+mod edit;
+mod movecommand;
+mod pane;
+mod system;
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Command {
+    Move(Move, SelectionMode),
+    Edit(Edit),
+    System(System),
+    Clipboard(Clipboard),
+    Pane(Pane),
+    BracketedPaste(String),
+}
+
+// Looks a command up by the name it's bound to in the keymap
+impl TryFrom<&str> for Command {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        Edit::try_from(name)
+            .map(Command::Edit)
+            .or_else(|_| {
+                Move::try_from(name).map(|command| Command::Move(command, SelectionMode::Collapse))
+            })
+            .or_else(|_| System::try_from(name).map(Command::System))
+            .or_else(|_| Clipboard::try_from(name).map(Command::Clipboard))
+            .or_else(|_| Pane::try_from(name).map(Command::Pane))
+            .map_err(|_err| format!("Unknown command: {name}"))
+    }
+}
+
+// Key presses aren't commands by themselves, they are looked up in the `Keymap`
+#[allow(clippy::as_conversions)]
+impl TryFrom<Event> for Command {
+    type Error = String;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
+                width: width_u16 as usize,
+                height: height_u16 as usize,
+            }))),
+            // Terminals commonly send line breaks inside a paste as carriage returns
+            Event::Paste(text) => Ok(Self::BracketedPaste(
+                text.replace("\r\n", "\n").replace('\r', "\n"),
+            )),
+            _ => Err(format!("Event not supported: {event:?}")),
+        }
+    }
+}
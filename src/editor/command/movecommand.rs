@@ -1,42 +1,51 @@
-use crossterm::event::{
-    KeyCode::{Down, End, Home, Left, PageDown, PageUp, Right, Up},
-    KeyEvent, KeyModifiers,
-};
-
-#[derive(Clone, Copy)]
-pub enum Move {
-    PageUp,
-    PageDown,
-    StartOfLine,
-    EndOfLine,
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl TryFrom<KeyEvent> for Move {
-    type Error = String;
-
-    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-        let KeyEvent {
-            code, modifiers, ..
-        } = event;
-
-        if modifiers == KeyModifiers::NONE {
-            match code {
-                Up => Ok(Self::Up),
-                Down => Ok(Self::Down),
-                Left => Ok(Self::Left),
-                Right => Ok(Self::Right),
-                PageDown => Ok(Self::PageDown),
-                PageUp => Ok(Self::PageUp),
-                Home => Ok(Self::StartOfLine),
-                End => Ok(Self::EndOfLine),
-                _ => Err(format!("Unknown move: {code:?}")),
-            }
-        } else {
-            Err(format!("Unknown move: {code:?} or {modifiers:?}"))
-        }
-    }
-}
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    PageUp,
+    PageDown,
+    StartOfLine,
+    EndOfLine,
+    Up,
+    Down,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    StartOfFile,
+    EndOfFile,
+    PreviousParagraph,
+    NextParagraph,
+    MatchingBracket,
+}
+
+// Whether a move extends the current selection or collapses it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+    Collapse,
+    Extend,
+}
+
+impl TryFrom<&str> for Move {
+    type Error = String;
+
+    // Moves are bound without Shift, the keymap adds it to extend the selection, see `SelectionMode`
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "page_up" => Ok(Self::PageUp),
+            "page_down" => Ok(Self::PageDown),
+            "start_of_line" => Ok(Self::StartOfLine),
+            "end_of_line" => Ok(Self::EndOfLine),
+            "word_left" => Ok(Self::WordLeft),
+            "word_right" => Ok(Self::WordRight),
+            "start_of_file" => Ok(Self::StartOfFile),
+            "end_of_file" => Ok(Self::EndOfFile),
+            "previous_paragraph" => Ok(Self::PreviousParagraph),
+            "next_paragraph" => Ok(Self::NextParagraph),
+            "matching_bracket" => Ok(Self::MatchingBracket),
+            _ => Err(format!("Unknown move: {name}")),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    SplitHorizontal,
+    SplitVertical,
+    Close,
+    FocusNext,
+}
+
+impl TryFrom<&str> for Pane {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "split_horizontal" => Ok(Self::SplitHorizontal),
+            "split_vertical" => Ok(Self::SplitVertical),
+            "close_pane" => Ok(Self::Close),
+            "next_pane" => Ok(Self::FocusNext),
+            _ => Err(format!("Unknown pane command: {name}")),
+        }
+    }
+}
@@ -1,38 +1,53 @@
-use crate::prelude::*;
-use crossterm::event::{
-    KeyCode::{self, Char},
-    KeyEvent, KeyModifiers,
-};
-
-#[derive(Clone, Copy)]
-pub enum System {
-    Save,
-    Search,
-    Resize(Size),
-    Quit,
-    Dismiss,
-}
-
-impl TryFrom<KeyEvent> for System {
-    type Error = String;
-    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-        let KeyEvent {
-            code, modifiers, ..
-        } = event;
-
-        if modifiers == KeyModifiers::CONTROL {
-            match code {
-                Char('q') => Ok(Self::Quit),
-                Char('s') => Ok(Self::Save),
-                Char('f') => Ok(Self::Search),
-                _ => Err(format!("Unknown CONTROL+{code:?} combination")),
-            }
-        } else if modifiers == KeyModifiers::NONE && matches!(code, KeyCode::Esc) {
-            Ok(Self::Dismiss)
-        } else {
-            Err(format!(
-                "Unknown key code {code:?} or modifier {modifiers:?}"
-            ))
-        }
-    }
-}
+use crate::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum System {
+    Save,
+    Search,
+    Replace,
+    GoToLine,
+    ToggleLineEnding,
+    ToggleSoftWrap,
+    CycleLineNumbers,
+    Reload,
+    Open,
+    NextBuffer,
+    PreviousBuffer,
+    CloseBuffer,
+    ListBuffers,
+    // search prompt options
+    ToggleRegex,
+    CycleCaseSensitivity,
+    ToggleWholeWord,
+    Resize(Size),
+    Quit,
+    Dismiss,
+}
+
+impl TryFrom<&str> for System {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "quit" => Ok(Self::Quit),
+            "save" => Ok(Self::Save),
+            "search" => Ok(Self::Search),
+            "replace" => Ok(Self::Replace),
+            "go_to_line" => Ok(Self::GoToLine),
+            "toggle_line_ending" => Ok(Self::ToggleLineEnding),
+            "reload" => Ok(Self::Reload),
+            "open" => Ok(Self::Open),
+            "next_buffer" => Ok(Self::NextBuffer),
+            "previous_buffer" => Ok(Self::PreviousBuffer),
+            "close_buffer" => Ok(Self::CloseBuffer),
+            "list_buffers" => Ok(Self::ListBuffers),
+            "toggle_regex" => Ok(Self::ToggleRegex),
+            "cycle_case_sensitivity" => Ok(Self::CycleCaseSensitivity),
+            "toggle_whole_word" => Ok(Self::ToggleWholeWord),
+            "toggle_soft_wrap" => Ok(Self::ToggleSoftWrap),
+            "cycle_line_numbers" => Ok(Self::CycleLineNumbers),
+            "dismiss" => Ok(Self::Dismiss),
+            _ => Err(format!("Unknown system command: {name}")),
+        }
+    }
+}
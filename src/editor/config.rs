@@ -0,0 +1,254 @@
+use super::{FileType, LineNumbers, Theme};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::ErrorKind,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use toml::{Table, Value};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+// Settings that only make sense for the editor as a whole, not for a single file type
+const GLOBAL_SETTINGS: [&str; 4] = ["theme", "quit_times", "auto_save_interval", "keep_backup"];
+
+// What the tab key inserts
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Indent {
+    #[default]
+    Tabs,
+    Spaces, // up to the next tab stop
+}
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tabs" => Ok(Self::Tabs),
+            "spaces" => Ok(Self::Spaces),
+            _ => Err(format!("expected \"tabs\" or \"spaces\", not \"{value}\"")),
+        }
+    }
+}
+
+// Settings that can be set differently for each file type
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub tab_width: usize,
+    pub show_tabs: bool, // as an arrow
+    pub indent: Indent,
+    pub soft_wrap: bool,
+    pub line_numbers: LineNumbers,
+    pub scroll_margin: usize, // rows kept between the caret and the top or bottom of the view
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            show_tabs: false,
+            indent: Indent::default(),
+            soft_wrap: false,
+            line_numbers: LineNumbers::default(),
+            scroll_margin: 0,
+        }
+    }
+}
+
+impl Settings {
+    fn apply(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        match key {
+            "tab_width" => self.tab_width = parse_number(value, 1..=16)?,
+            "show_tabs" => self.show_tabs = parse_bool(value)?,
+            "indent" => self.indent = parse_str(value)?,
+            "soft_wrap" => self.soft_wrap = parse_bool(value)?,
+            "line_numbers" => self.line_numbers = parse_str(value)?,
+            "scroll_margin" => self.scroll_margin = parse_number(value, 0..=100)?,
+            _ => return Err("unknown setting".to_string()),
+        }
+        Ok(())
+    }
+}
+
+// The settings from `config.toml` in the config directory. The settings for all file types are at the top,
+// tables such as `[filetype.rust]` override them for one file type.
+pub struct Config {
+    pub theme: Theme,
+    pub quit_times: u8, // how often Ctrl-Q has to be pressed to quit with unsaved changes
+    pub auto_save_interval: Duration, // without a key press, after which the swap file is written
+    pub keep_backup: bool,
+    settings: Settings,
+    file_type_settings: HashMap<FileType, Settings>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            quit_times: 3,
+            auto_save_interval: Duration::from_millis(1500),
+            keep_backup: false,
+            settings: Settings::default(),
+            file_type_settings: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    // The defaults with the settings from the config file applied on top.
+    // Also returns what was wrong with the file, settings that have problems keep their defaults.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let Some(file) = config_dir().map(|dir| dir.join(CONFIG_FILE_NAME)) else {
+            return (config, Vec::new());
+        };
+
+        let problems = match fs::read_to_string(&file) {
+            Ok(contents) => config.apply(&contents),
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => vec![format!("could not read {}: {err}", file.display())],
+        };
+
+        (config, problems)
+    }
+
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let table = match parse_table(contents) {
+            Ok(table) => table,
+            Err(err) => return vec![err],
+        };
+
+        let mut problems = Vec::new();
+        let mut file_types = None;
+
+        for (key, value) in &table {
+            let result = match key.as_str() {
+                "theme" => parse_str(value).map(|theme| self.theme = theme),
+                "quit_times" => parse_number(value, 1..=10).map(|times| self.quit_times = times),
+                "auto_save_interval" => parse_number(value, 100..=60_000)
+                    .map(|millis| self.auto_save_interval = Duration::from_millis(millis)),
+                "keep_backup" => parse_bool(value).map(|keep| self.keep_backup = keep),
+                "filetype" => {
+                    file_types = Some(value);
+                    Ok(())
+                }
+                _ => self.settings.apply(key, value),
+            };
+            if let Err(err) = result {
+                problems.push(format!("{key}: {err}"));
+            }
+        }
+
+        // applied last, so file types start out from all the settings for every file type
+        match file_types.map(Value::as_table) {
+            None => {}
+            Some(None) => {
+                problems.push("filetype: expected tables like [filetype.rust]".to_string())
+            }
+            Some(Some(file_types)) => {
+                for (name, value) in file_types {
+                    problems.extend(self.apply_file_type(name, value));
+                }
+            }
+        }
+
+        problems
+    }
+
+    fn apply_file_type(&mut self, name: &str, value: &Value) -> Vec<String> {
+        let file_type = match name.parse::<FileType>() {
+            Ok(file_type) => file_type,
+            Err(err) => return vec![format!("filetype.{name}: {err}")],
+        };
+        let Some(table) = value.as_table() else {
+            return vec![format!("filetype.{name}: expected a table of settings")];
+        };
+
+        let mut problems = Vec::new();
+        let mut settings = self.settings;
+        for (key, value) in table {
+            let result = if GLOBAL_SETTINGS.contains(&key.as_str()) {
+                Err("can only be set for all file types".to_string())
+            } else {
+                settings.apply(key, value)
+            };
+            if let Err(err) = result {
+                problems.push(format!("filetype.{name}.{key}: {err}"));
+            }
+        }
+        self.file_type_settings.insert(file_type, settings);
+
+        problems
+    }
+
+    pub fn settings(&self, file_type: FileType) -> Settings {
+        self.file_type_settings
+            .get(&file_type)
+            .copied()
+            .unwrap_or(self.settings)
+    }
+}
+
+// Parse errors point at the line they were found on
+pub fn parse_table(contents: &str) -> Result<Table, String> {
+    contents.parse::<Table>().map_err(|err| {
+        let line = err.span().map_or(1, |span| {
+            contents
+                .get(..span.start)
+                .unwrap_or_default()
+                .matches('\n')
+                .count()
+                .saturating_add(1)
+        });
+        // the message can run over several lines, which the message bar can't show
+        let message = err
+            .message()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("line {line}: {message}")
+    })
+}
+
+fn parse_number<T: TryFrom<i64>>(value: &Value, range: RangeInclusive<i64>) -> Result<T, String> {
+    value
+        .as_integer()
+        .filter(|number| range.contains(number))
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| {
+            format!(
+                "expected a whole number from {} to {}, not {value}",
+                range.start(),
+                range.end()
+            )
+        })
+}
+
+fn parse_bool(value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("expected true or false, not {value}"))
+}
+
+fn parse_str<T: FromStr<Err = String>>(value: &Value) -> Result<T, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("expected a string, not {value}"))?
+        .parse()
+}
+
+// `$XDG_CONFIG_HOME/hecto`, or `~/.config/hecto` if it isn't set
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+    Some(base.join("hecto"))
+}
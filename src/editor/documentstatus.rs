@@ -1,38 +1,51 @@
-use super::FileType;
-use crate::prelude::*;
-
-#[derive(Default, Eq, PartialEq, Debug)]
-pub struct DocumentStatus {
-    pub current_grapheme_index: GraphemeIdx,
-    pub current_line_index: LineIdx,
-    pub file_name: String,
-    pub file_type: FileType,
-    pub is_modified: bool,
-    pub total_lines: usize,
-}
-
-impl DocumentStatus {
-    pub fn modified_indicator_to_string(&self) -> String {
-        if self.is_modified {
-            String::from("(modified)")
-        } else {
-            String::new()
-        }
-    }
-
-    pub fn line_count_to_string(&self) -> String {
-        format!("{} lines", self.total_lines)
-    }
-
-    pub fn position_indicator_to_string(&self) -> String {
-        format!(
-            "{}:{}",
-            self.current_line_index.saturating_add(1),
-            self.current_grapheme_index.saturating_add(1)
-        )
-    }
-
-    pub fn file_type_to_string(&self) -> String {
-        format!("{}", self.file_type)
-    }
-}
+use super::{Encoding, FileType, LineEnding};
+use crate::prelude::*;
+
+#[derive(Default, Eq, PartialEq, Debug)]
+pub struct DocumentStatus {
+    pub current_grapheme_index: GraphemeIdx,
+    pub current_line_index: LineIdx,
+    pub file_name: String,
+    pub file_type: FileType,
+    pub is_modified: bool,
+    pub is_read_only: bool,
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+    pub total_lines: usize,
+}
+
+impl DocumentStatus {
+    pub fn modified_indicator_to_string(&self) -> String {
+        if self.is_read_only {
+            String::from("(read-only)")
+        } else if self.is_modified {
+            String::from("(modified)")
+        } else {
+            String::new()
+        }
+    }
+
+    pub fn line_count_to_string(&self) -> String {
+        format!("{} lines", self.total_lines)
+    }
+
+    pub fn position_indicator_to_string(&self) -> String {
+        format!(
+            "{}:{}",
+            self.current_line_index.saturating_add(1),
+            self.current_grapheme_index.saturating_add(1)
+        )
+    }
+
+    pub fn file_type_to_string(&self) -> String {
+        format!("{}", self.file_type)
+    }
+
+    pub fn encoding_to_string(&self) -> String {
+        format!("{}", self.encoding)
+    }
+
+    pub fn line_ending_to_string(&self) -> String {
+        format!("{}", self.line_ending)
+    }
+}
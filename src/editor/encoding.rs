@@ -0,0 +1,299 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{Error, ErrorKind, Write},
+};
+
+// Characters for the bytes 0x80 to 0x9F in Windows-1252. The five bytes the code page leaves undefined
+// map to the matching C1 control characters, so that any byte sequence survives decoding and encoding again.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+// How many bytes at the start of a file are inspected to guess its encoding
+const SNIFF_LEN: usize = 8000;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+// The result of decoding a file
+pub struct Decoded {
+    pub text: String,
+    pub encoding: Encoding,
+    pub is_binary: bool, // the contents don't look like text, or couldn't be decoded without losing data
+}
+
+impl Encoding {
+    pub fn decode(bytes: Vec<u8>) -> Decoded {
+        let utf16_bom = match bytes.as_slice() {
+            [0xFF, 0xFE, ..] => Some(Self::Utf16Le),
+            [0xFE, 0xFF, ..] => Some(Self::Utf16Be),
+            _ => None,
+        };
+
+        if let Some(encoding) = utf16_bom {
+            let (text, is_lossy) = encoding.decode_utf16(&bytes);
+            return Decoded {
+                text,
+                encoding,
+                is_binary: is_lossy,
+            };
+        }
+
+        if let Some(encoding) = Self::guess_utf16(&bytes) {
+            let (text, is_lossy) = encoding.decode_utf16(&bytes);
+
+            // without a byte order mark it's only a guess, which binary data can easily trip
+            let has_controls = text.chars().any(|character| {
+                character.is_control() && !matches!(character, '\t' | '\n' | '\r')
+            });
+            if !is_lossy && !has_controls {
+                return Decoded {
+                    text,
+                    encoding,
+                    is_binary: false,
+                };
+            }
+        }
+
+        let sniffed = &bytes[..bytes.len().min(SNIFF_LEN)];
+        let is_binary = sniffed.contains(&0);
+
+        match String::from_utf8(bytes) {
+            Ok(text) => Decoded {
+                text,
+                encoding: Self::Utf8,
+                is_binary,
+            },
+            Err(err) => Decoded {
+                text: Self::decode_windows_1252(err.as_bytes()),
+                encoding: Self::Windows1252,
+                is_binary,
+            },
+        }
+    }
+
+    // Looks for the zero bytes ASCII characters leave in every other position in UTF-16
+    fn guess_utf16(bytes: &[u8]) -> Option<Self> {
+        let sniffed = &bytes[..bytes.len().min(SNIFF_LEN)];
+        if sniffed.len() < 2 || sniffed.len() % 2 != 0 {
+            return None;
+        }
+
+        let pairs = sniffed.len() / 2;
+        let zeros_at = |offset: usize| {
+            sniffed
+                .iter()
+                .skip(offset)
+                .step_by(2)
+                .filter(|byte| **byte == 0)
+                .count()
+        };
+        let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+
+        // at least 40% of the characters are ASCII, and (almost) none of them has zeros on the other side
+        if odd_zeros.saturating_mul(5) >= pairs.saturating_mul(2)
+            && even_zeros.saturating_mul(20) < pairs
+        {
+            Some(Self::Utf16Le)
+        } else if even_zeros.saturating_mul(5) >= pairs.saturating_mul(2)
+            && odd_zeros.saturating_mul(20) < pairs
+        {
+            Some(Self::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    // Returns the decoded text and whether anything had to be replaced to decode it
+    fn decode_utf16(self, bytes: &[u8]) -> (String, bool) {
+        let units = bytes.chunks(2).map(|chunk| {
+            let pair = [chunk[0], chunk.get(1).copied().unwrap_or_default()];
+            if self == Self::Utf16Be {
+                u16::from_be_bytes(pair)
+            } else {
+                u16::from_le_bytes(pair)
+            }
+        });
+
+        let mut is_lossy = bytes.len() % 2 != 0;
+        let text = char::decode_utf16(units)
+            .map(|result| {
+                result.unwrap_or_else(|_| {
+                    is_lossy = true;
+                    char::REPLACEMENT_CHARACTER
+                })
+            })
+            .collect();
+
+        (text, is_lossy)
+    }
+
+    fn decode_windows_1252(bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+                _ => char::from(byte),
+            })
+            .collect()
+    }
+
+    fn encode_windows_1252(character: char) -> Option<u8> {
+        if let Ok(byte) = u8::try_from(character) {
+            if !(0x80..=0x9F).contains(&byte) {
+                return Some(byte);
+            }
+        }
+
+        WINDOWS_1252_HIGH
+            .iter()
+            .position(|&high| high == character)
+            .and_then(|idx| u8::try_from(idx).ok())
+            .map(|idx| idx.saturating_add(0x80))
+    }
+
+    // Writes the text in this encoding. Fails if a character can't be represented in it.
+    pub fn write(self, writer: &mut impl Write, text: &str) -> Result<(), Error> {
+        match self {
+            Self::Utf8 => writer.write_all(text.as_bytes()),
+            Self::Utf16Le => {
+                let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                writer.write_all(&bytes)
+            }
+            Self::Utf16Be => {
+                let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+                writer.write_all(&bytes)
+            }
+            Self::Windows1252 => {
+                let bytes = text
+                    .chars()
+                    .map(|character| {
+                        Self::encode_windows_1252(character).ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::InvalidData,
+                                format!("'{character}' can't be encoded as {self}"),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<u8>, Error>>()?;
+                writer.write_all(&bytes)
+            }
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "UTF-8"),
+            Self::Utf16Le => write!(f, "UTF-16LE"),
+            Self::Utf16Be => write!(f, "UTF-16BE"),
+            Self::Windows1252 => write!(f, "Windows-1252"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "fn main() {\r\n    println!(\"héllo, wörld 🦀\");\n}\n";
+
+    fn encode(encoding: Encoding, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encoding.write(&mut bytes, text).unwrap();
+        bytes
+    }
+
+    // Decodes the bytes, checks what they were taken for, and that encoding them again gives the same bytes
+    fn assert_round_trip(bytes: &[u8], encoding: Encoding) -> String {
+        let decoded = Encoding::decode(bytes.to_vec());
+        assert_eq!(decoded.encoding, encoding);
+        assert!(!decoded.is_binary);
+        assert_eq!(encode(decoded.encoding, &decoded.text), bytes);
+        decoded.text
+    }
+
+    #[test]
+    fn utf8_round_trips() {
+        assert_eq!(assert_round_trip(TEXT.as_bytes(), Encoding::Utf8), TEXT);
+    }
+
+    #[test]
+    fn utf16_with_byte_order_mark_round_trips() {
+        let text = format!("\u{FEFF}{TEXT}");
+        for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = encode(encoding, &text);
+            assert_eq!(assert_round_trip(&bytes, encoding), text);
+        }
+    }
+
+    #[test]
+    fn utf16_without_byte_order_mark_round_trips() {
+        for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = encode(encoding, TEXT);
+            assert_eq!(assert_round_trip(&bytes, encoding), TEXT);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_windows_1252() {
+        let bytes = b"caf\xE9 \x80 \x93quoted\x94 \x81\x8D\x8F\x90\x9D\n";
+        assert_eq!(
+            assert_round_trip(bytes, Encoding::Windows1252),
+            "café € “quoted” \u{81}\u{8D}\u{8F}\u{90}\u{9D}\n"
+        );
+    }
+
+    #[test]
+    fn every_byte_but_zero_round_trips_as_windows_1252() {
+        let bytes: Vec<u8> = (1..=u8::MAX).collect();
+        assert_round_trip(&bytes, Encoding::Windows1252);
+    }
+
+    #[test]
+    fn characters_outside_windows_1252_are_refused() {
+        let mut bytes = Vec::new();
+        assert!(Encoding::Windows1252.write(&mut bytes, "🦀").is_err());
+    }
+
+    #[test]
+    fn zero_bytes_mark_the_file_as_binary() {
+        let decoded =
+            Encoding::decode(b"\x7FELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec());
+        assert!(decoded.is_binary);
+
+        let decoded = Encoding::decode(b"caf\xE9\x00".to_vec());
+        assert_eq!(decoded.encoding, Encoding::Windows1252);
+        assert!(decoded.is_binary);
+    }
+
+    #[test]
+    fn broken_utf16_is_binary() {
+        // an odd number of bytes
+        let mut bytes = encode(Encoding::Utf16Le, "\u{FEFF}text");
+        bytes.push(b'x');
+        assert!(Encoding::decode(bytes).is_binary);
+
+        // an unpaired surrogate
+        let bytes = [0xFE, 0xFF, 0xD8, 0x00, 0x00, b'a'];
+        assert!(Encoding::decode(bytes.to_vec()).is_binary);
+    }
+
+    #[test]
+    fn control_characters_are_not_taken_for_utf16() {
+        let bytes = [0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00];
+        let decoded = Encoding::decode(bytes.to_vec());
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert!(decoded.is_binary);
+    }
+}
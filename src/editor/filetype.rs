@@ -1,17 +1,35 @@
-use std::fmt::{Display, Formatter, Result};
-
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub enum FileType {
-    Rust,
-    #[default]
-    Text,
-}
-
-impl Display for FileType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match self {
-            Self::Rust => write!(f, "Rust"),
-            Self::Text => write!(f, "Text"),
-        }
-    }
-}
+use std::{
+    fmt::{Display, Formatter, Result},
+    str::FromStr,
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum FileType {
+    Rust,
+    #[default]
+    Text,
+}
+
+impl Display for FileType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Rust => write!(f, "Rust"),
+            Self::Text => write!(f, "Text"),
+        }
+    }
+}
+
+// As written in the config file
+impl FromStr for FileType {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "rust" => Ok(Self::Rust),
+            "text" => Ok(Self::Text),
+            _ => Err(format!(
+                "unknown file type \"{value}\", expected \"rust\" or \"text\""
+            )),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::prelude::*;
+use std::{path::Path, str::FromStr};
+
+// Where to take the caret, as typed into the go to line prompt or given on the command line
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GoTo {
+    Line { line: usize, col: Option<usize> }, // both count from 1
+    Offset(isize),                            // lines down from the caret, up if negative
+    Percent(usize),                           // of the way through the document
+}
+
+impl GoTo {
+    // The location to go to from the caret's line in a document of `line_count` lines.
+    // Lines past the end go to the last one, columns past the end are left for the view to snap back.
+    pub fn resolve(self, caret_line_idx: LineIdx, line_count: usize) -> Location {
+        let line_index = match self {
+            Self::Line { line, .. } => line.saturating_sub(1),
+            Self::Offset(offset) => caret_line_idx.saturating_add_signed(offset),
+            Self::Percent(percent) => line_count
+                .saturating_mul(percent.min(100))
+                .div_ceil(100)
+                .saturating_sub(1),
+        }
+        .min(line_count.saturating_sub(1));
+
+        let grapheme_index = match self {
+            Self::Line { col: Some(col), .. } => col.saturating_sub(1),
+            _ => 0,
+        };
+
+        Location {
+            line_index,
+            grapheme_index,
+        }
+    }
+
+    // Splits a trailing `:line` or `:line:col` off a file name given on the command line,
+    // unless a file by the full name exists
+    pub fn split_file_name(arg: &str) -> (&str, Option<Self>) {
+        if Path::new(arg).exists() {
+            return (arg, None);
+        }
+
+        let Some((rest, last)) = arg.rsplit_once(':') else {
+            return (arg, None);
+        };
+        let Ok(last) = last.parse() else {
+            return (arg, None);
+        };
+
+        if let Some((file_name, line)) = rest.rsplit_once(':') {
+            if let Ok(line) = line.parse() {
+                return (
+                    file_name,
+                    Some(Self::Line {
+                        line,
+                        col: Some(last),
+                    }),
+                );
+            }
+        }
+
+        (
+            rest,
+            Some(Self::Line {
+                line: last,
+                col: None,
+            }),
+        )
+    }
+}
+
+impl FromStr for GoTo {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let invalid = || format!("expected line, line:col, +N, -N or N%, not \"{value}\"");
+        let parse_offset = |offset: &str| {
+            offset
+                .parse::<usize>()
+                .ok()
+                .and_then(|offset| isize::try_from(offset).ok())
+                .ok_or_else(invalid)
+        };
+
+        if let Some(percent) = value.strip_suffix('%') {
+            percent.parse().map(Self::Percent).map_err(|_| invalid())
+        } else if let Some(offset) = value.strip_prefix('+') {
+            parse_offset(offset).map(Self::Offset)
+        } else if let Some(offset) = value.strip_prefix('-') {
+            parse_offset(offset).map(|offset| Self::Offset(offset.saturating_neg()))
+        } else if let Some((line, col)) = value.split_once(':') {
+            match (line.parse(), col.parse()) {
+                (Ok(line), Ok(col)) => Ok(Self::Line {
+                    line,
+                    col: Some(col),
+                }),
+                _ => Err(invalid()),
+            }
+        } else {
+            value
+                .parse()
+                .map(|line| Self::Line { line, col: None })
+                .map_err(|_| invalid())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> GoTo {
+        value.parse().unwrap()
+    }
+
+    const fn location(line_index: LineIdx, grapheme_index: GraphemeIdx) -> Location {
+        Location {
+            line_index,
+            grapheme_index,
+        }
+    }
+
+    #[test]
+    fn parses_a_line() {
+        assert_eq!(
+            parse("12"),
+            GoTo::Line {
+                line: 12,
+                col: None
+            }
+        );
+        assert_eq!(
+            parse(" 12 "),
+            GoTo::Line {
+                line: 12,
+                col: None
+            }
+        );
+    }
+
+    #[test]
+    fn parses_a_line_and_column() {
+        assert_eq!(
+            parse("12:5"),
+            GoTo::Line {
+                line: 12,
+                col: Some(5)
+            }
+        );
+    }
+
+    #[test]
+    fn parses_offsets_and_percentages() {
+        assert_eq!(parse("+3"), GoTo::Offset(3));
+        assert_eq!(parse("-3"), GoTo::Offset(-3));
+        assert_eq!(parse("50%"), GoTo::Percent(50));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for value in [
+            "", "abc", "12:", ":5", "1:2:3", "12:x", "+", "-x", "%", "1.5", "+-3", "--3",
+        ] {
+            assert!(value.parse::<GoTo>().is_err(), "{value:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn resolves_lines_counting_from_one() {
+        assert_eq!(parse("1").resolve(5, 10), location(0, 0));
+        assert_eq!(parse("4:7").resolve(5, 10), location(3, 6));
+        assert_eq!(parse("+2").resolve(5, 10), location(7, 0));
+        assert_eq!(parse("-2").resolve(5, 10), location(3, 0));
+        assert_eq!(parse("50%").resolve(5, 10), location(4, 0));
+    }
+
+    #[test]
+    fn out_of_range_lines_stay_in_the_document() {
+        assert_eq!(parse("0").resolve(5, 10), location(0, 0));
+        assert_eq!(parse("99").resolve(5, 10), location(9, 0));
+        assert_eq!(parse("+99").resolve(5, 10), location(9, 0));
+        assert_eq!(parse("-99").resolve(5, 10), location(0, 0));
+        assert_eq!(parse("250%").resolve(5, 10), location(9, 0));
+        assert_eq!(parse("0%").resolve(5, 10), location(0, 0));
+        assert_eq!(parse("3").resolve(0, 0), location(0, 0));
+    }
+
+    #[test]
+    fn columns_past_the_end_are_left_to_the_view() {
+        assert_eq!(parse("2:500").resolve(0, 10), location(1, 499));
+    }
+
+    #[test]
+    fn splits_line_and_column_off_file_names() {
+        assert_eq!(
+            GoTo::split_file_name("missing.rs:12:5"),
+            (
+                "missing.rs",
+                Some(GoTo::Line {
+                    line: 12,
+                    col: Some(5)
+                })
+            )
+        );
+        assert_eq!(
+            GoTo::split_file_name("missing.rs:12"),
+            (
+                "missing.rs",
+                Some(GoTo::Line {
+                    line: 12,
+                    col: None
+                })
+            )
+        );
+        assert_eq!(
+            GoTo::split_file_name("dir:name/missing.rs:3:4"),
+            (
+                "dir:name/missing.rs",
+                Some(GoTo::Line {
+                    line: 3,
+                    col: Some(4)
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_file_names_without_a_position() {
+        assert_eq!(GoTo::split_file_name("missing.rs"), ("missing.rs", None));
+        assert_eq!(GoTo::split_file_name("missing.rs:"), ("missing.rs:", None));
+        assert_eq!(GoTo::split_file_name("missing:rs"), ("missing:rs", None));
+        assert_eq!(GoTo::split_file_name("Cargo.toml"), ("Cargo.toml", None));
+    }
+}
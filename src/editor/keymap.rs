@@ -0,0 +1,715 @@
+use super::{
+    command::{Command, Edit, SelectionMode},
+    config::{config_dir, parse_table},
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    io::ErrorKind,
+    str::FromStr,
+};
+
+const KEYMAP_FILE_NAME: &str = "keys.toml";
+
+// Bindings are written as the keys to press, separated by spaces for a sequence, and the name of the
+// command to run, e.g. `"ctrl-k ctrl-c" = "copy"`. Letters ignore case, `shift-a` is an upper case A.
+// Moves are bound without Shift, pressing it along extends the selection.
+const DEFAULT_BINDINGS: [(&str, &str); 50] = [
+    ("ctrl-q", "quit"),
+    ("ctrl-s", "save"),
+    ("ctrl-f", "search"),
+    ("ctrl-t", "replace"),
+    ("ctrl-g", "go_to_line"),
+    ("ctrl-e", "toggle_line_ending"),
+    ("ctrl-r", "reload"),
+    ("ctrl-o", "open"),
+    ("ctrl-n", "next_buffer"),
+    ("ctrl-p", "previous_buffer"),
+    ("ctrl-w", "close_buffer"),
+    ("ctrl-b", "list_buffers"),
+    ("alt-r", "toggle_regex"),
+    ("alt-c", "cycle_case_sensitivity"),
+    ("alt-b", "toggle_whole_word"),
+    ("alt-z", "toggle_soft_wrap"),
+    ("alt-l", "cycle_line_numbers"),
+    ("esc", "dismiss"),
+    ("ctrl-x", "cut"),
+    ("ctrl-c", "copy"),
+    ("ctrl-v", "paste"),
+    ("alt-s", "split_horizontal"),
+    ("alt-v", "split_vertical"),
+    ("alt-w", "close_pane"),
+    ("alt-o", "next_pane"),
+    ("tab", "insert_tab"),
+    ("enter", "insert_newline"),
+    ("backspace", "delete_backward"),
+    ("delete", "delete"),
+    // most terminals send Ctrl+Backspace as Ctrl-H
+    ("ctrl-backspace", "delete_word_backward"),
+    ("ctrl-h", "delete_word_backward"),
+    ("ctrl-delete", "delete_word"),
+    ("ctrl-z", "undo"),
+    ("ctrl-y", "redo"),
+    ("up", "up"),
+    ("down", "down"),
+    ("left", "left"),
+    ("right", "right"),
+    ("pageup", "page_up"),
+    ("pagedown", "page_down"),
+    ("home", "start_of_line"),
+    ("end", "end_of_line"),
+    ("ctrl-left", "word_left"),
+    ("ctrl-right", "word_right"),
+    ("ctrl-home", "start_of_file"),
+    ("ctrl-end", "end_of_file"),
+    ("ctrl-up", "previous_paragraph"),
+    ("ctrl-down", "next_paragraph"),
+    // most terminals send Ctrl-] as the same byte as Ctrl-5
+    ("ctrl-]", "matching_bracket"),
+    ("ctrl-5", "matching_bracket"),
+];
+
+// What the help message lists: commands shown together, what each is called there, and what follows
+const HELP_ENTRIES: [(&[(&str, &str)], &str); 13] = [
+    (&[("search", "find")], ""),
+    (&[("replace", "replace")], ""),
+    (&[("go_to_line", "go to line")], ""),
+    (&[("save", "save")], ""),
+    (&[("open", "open")], ""),
+    (
+        &[
+            ("next_buffer", "next"),
+            ("previous_buffer", "prev"),
+            ("close_buffer", "close"),
+            ("list_buffers", "list"),
+        ],
+        " buffers",
+    ),
+    (
+        &[
+            ("split_horizontal", "split"),
+            ("split_vertical", "vsplit"),
+            ("close_pane", "close"),
+            ("next_pane", "next"),
+        ],
+        " pane",
+    ),
+    (
+        &[
+            ("toggle_soft_wrap", "soft wrap"),
+            ("cycle_line_numbers", "line numbers"),
+        ],
+        "",
+    ),
+    (&[("reload", "reload")], ""),
+    (&[("toggle_line_ending", "line endings")], ""),
+    (&[("undo", "undo"), ("redo", "redo")], ""),
+    (&[("cut", "cut"), ("copy", "copy"), ("paste", "paste")], ""),
+    (&[("quit", "quit")], ""),
+];
+
+// A key together with the modifiers held down. Shift is part of the character for character keys,
+// so Shift-A and a typed A are the same chord.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers.intersection(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+
+        match code {
+            KeyCode::BackTab => Self {
+                code: KeyCode::Tab,
+                modifiers: modifiers.union(KeyModifiers::SHIFT),
+            },
+            KeyCode::Char(character) => {
+                let character = if modifiers.contains(KeyModifiers::SHIFT) {
+                    to_upper_case(character)
+                } else {
+                    character
+                };
+                Self {
+                    code: KeyCode::Char(character),
+                    modifiers: modifiers.difference(KeyModifiers::SHIFT),
+                }
+            }
+            _ => Self { code, modifiers },
+        }
+    }
+
+    fn without_shift(self) -> Option<Self> {
+        self.modifiers
+            .contains(KeyModifiers::SHIFT)
+            .then(|| Self::new(self.code, self.modifiers.difference(KeyModifiers::SHIFT)))
+    }
+}
+
+fn to_upper_case(character: char) -> char {
+    let mut upper = character.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => character,
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    // Modifiers come first, so `ctrl--` is Ctrl with the minus key
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = value;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, "ctrl-".len())
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, "alt-".len())
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, "shift-".len())
+            } else {
+                break;
+            };
+            modifiers.insert(modifier);
+            rest = rest.get(len..).unwrap_or_default();
+        }
+
+        let mut characters = rest.chars();
+        let code = match (characters.next(), characters.next()) {
+            (Some(character), None) => {
+                KeyCode::Char(character.to_lowercase().next().unwrap_or(character))
+            }
+            _ => match rest.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => name
+                    .strip_prefix('f')
+                    .and_then(|number| number.parse().ok())
+                    .filter(|number| (1..=12).contains(number))
+                    .map(KeyCode::F)
+                    .ok_or_else(|| format!("unknown key \"{value}\""))?,
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(character) if character.is_uppercase() => write!(f, "Shift-{character}"),
+            KeyCode::Char(character) => write!(f, "{}", to_upper_case(character)),
+            KeyCode::F(number) => write!(f, "F{number}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+// Keys with the same modifiers are shown together, e.g. `Ctrl-X/C/V`
+fn join_keys(keys: &[String]) -> String {
+    let split: Vec<_> = keys.iter().map(|keys| split_modifiers(keys)).collect();
+    if let Some(Some((modifiers, _))) = split.first() {
+        if split
+            .iter()
+            .all(|part| matches!(part, Some((other, _)) if other == modifiers))
+        {
+            let keys: Vec<_> = split.iter().flatten().map(|(_, key)| *key).collect();
+            return format!("{modifiers}{}", keys.join("/"));
+        }
+    }
+    keys.join("/")
+}
+
+// Splits a single key into its modifiers and the key itself, e.g. `Ctrl-` and `X`
+fn split_modifiers(keys: &str) -> Option<(&str, &str)> {
+    if keys.contains(' ') {
+        return None;
+    }
+    let (last, _) = keys.char_indices().last()?;
+    let idx = keys[..last].rfind('-')?.saturating_add(1);
+    Some(keys.split_at(idx))
+}
+
+// The keys of a sequence as shown to the user, e.g. `Ctrl-K Ctrl-C`
+pub fn describe(sequence: &[KeyChord]) -> String {
+    sequence
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_sequence(value: &str) -> Result<Vec<KeyChord>, String> {
+    let sequence = value
+        .split_whitespace()
+        .map(KeyChord::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if sequence.is_empty() {
+        Err("no keys given".to_string())
+    } else {
+        Ok(sequence)
+    }
+}
+
+pub enum KeyLookup {
+    Command(Command),
+    Pending(Vec<KeyChord>), // the keys pressed so far of a longer sequence
+    Unbound(Vec<KeyChord>), // a sequence of more than one key that isn't bound
+    Ignored,                // a single key that isn't bound
+}
+
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Command>,
+    pending: Vec<KeyChord>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .filter_map(|(keys, name)| {
+                let sequence = parse_sequence(keys);
+                let command = Command::try_from(*name);
+                debug_assert!(
+                    sequence.is_ok() && command.is_ok(),
+                    "bad default binding {keys}"
+                );
+                Some((sequence.ok()?, command.ok()?))
+            })
+            .collect();
+
+        Self {
+            bindings,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Keymap {
+    // The default bindings with the ones from the keymap file applied on top.
+    // Also returns what was wrong with the file, bindings that have problems are left out.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let Some(file) = config_dir().map(|dir| dir.join(KEYMAP_FILE_NAME)) else {
+            return (keymap, Vec::new());
+        };
+
+        let problems = match fs::read_to_string(&file) {
+            Ok(contents) => keymap.apply(&contents),
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => vec![format!("could not read {}: {err}", file.display())],
+        };
+
+        (keymap, problems)
+    }
+
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let table = match parse_table(contents) {
+            Ok(table) => table,
+            Err(err) => return vec![err],
+        };
+
+        let mut problems = Vec::new();
+        // as written in the file, to report conflicts by what the user wrote
+        let mut user_bindings: Vec<(Vec<KeyChord>, &str)> = Vec::new();
+
+        for (keys, value) in &table {
+            let Some(name) = value.as_str() else {
+                problems.push(format!("\"{keys}\": expected the name of a command"));
+                continue;
+            };
+            let sequence = match parse_sequence(keys) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    problems.push(format!("\"{keys}\": {err}"));
+                    continue;
+                }
+            };
+            // "none" takes a default binding away
+            let command = if name == "none" {
+                None
+            } else {
+                match Command::try_from(name) {
+                    Ok(command) => Some(command),
+                    Err(_) => {
+                        problems.push(format!("\"{keys}\": unknown command \"{name}\""));
+                        continue;
+                    }
+                }
+            };
+
+            if let Some((_, other)) = user_bindings.iter().find(|(other, _)| *other == sequence) {
+                problems.push(format!("\"{other}\" and \"{keys}\" are the same keys"));
+                continue;
+            }
+            if let Some((_, other)) = user_bindings
+                .iter()
+                .find(|(other, _)| is_prefix(other, &sequence) || is_prefix(&sequence, other))
+            {
+                problems.push(format!(
+                    "\"{other}\" and \"{keys}\" conflict, one starts the other"
+                ));
+                continue;
+            }
+
+            // a sequence replaces the defaults it would hide, or that would hide it. Turning a key into
+            // the start of a sequence is what the user asked for, but a longer default is lost silently.
+            let hidden: Vec<_> = self
+                .bindings
+                .keys()
+                .filter(|other| is_prefix(other, &sequence) || is_prefix(&sequence, other))
+                .cloned()
+                .collect();
+            for other in hidden {
+                self.bindings.remove(&other);
+                if command.is_some() && other.len() > sequence.len() {
+                    problems.push(format!(
+                        "\"{keys}\" hides the default binding of {}",
+                        describe(&other)
+                    ));
+                }
+            }
+
+            self.bindings.remove(&sequence);
+            if let Some(command) = command {
+                self.bindings.insert(sequence.clone(), command);
+            }
+            user_bindings.push((sequence, keys));
+        }
+
+        problems
+    }
+
+    // The main commands with the keys they're bound to, e.g. `Ctrl-Z/Y = undo/redo`. Unbound commands are left out.
+    pub fn help(&self) -> String {
+        let entries: Vec<String> = HELP_ENTRIES
+            .iter()
+            .filter_map(|(commands, suffix)| {
+                let (keys, labels): (Vec<String>, Vec<&str>) = commands
+                    .iter()
+                    .filter_map(|(name, label)| Some((self.keys_for(name)?, *label)))
+                    .unzip();
+                (!keys.is_empty())
+                    .then(|| format!("{} = {}{suffix}", join_keys(&keys), labels.join("/")))
+            })
+            .collect();
+
+        format!("HELP: {}", entries.join(" | "))
+    }
+
+    // The keys bound to the command, the shortest if there are several
+    fn keys_for(&self, name: &str) -> Option<String> {
+        let command = Command::try_from(name).ok()?;
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == command)
+            .map(|(sequence, _)| describe(sequence))
+            .min_by(|keys, other| keys.len().cmp(&other.len()).then_with(|| keys.cmp(other)))
+    }
+
+    // Looks up the key together with the ones pressed before it
+    pub fn lookup(&mut self, event: KeyEvent) -> KeyLookup {
+        let chord = KeyChord::from(event);
+        let mut sequence = std::mem::take(&mut self.pending);
+        sequence.push(chord);
+
+        if let Some(command) = self.bindings.get(&sequence) {
+            return KeyLookup::Command(command.clone());
+        }
+        if self
+            .bindings
+            .keys()
+            .any(|other| is_prefix(&sequence, other))
+        {
+            self.pending.clone_from(&sequence);
+            return KeyLookup::Pending(sequence);
+        }
+        if sequence.len() > 1 {
+            return KeyLookup::Unbound(sequence);
+        }
+
+        // holding Shift with a move extends the selection
+        if let Some(Command::Move(command, _)) = chord
+            .without_shift()
+            .and_then(|chord| self.bindings.get(&vec![chord]))
+        {
+            return KeyLookup::Command(Command::Move(*command, SelectionMode::Extend));
+        }
+
+        match chord {
+            KeyChord {
+                code: KeyCode::Char(character),
+                modifiers: KeyModifiers::NONE,
+            } => KeyLookup::Command(Command::Edit(Edit::Insert(character))),
+            _ => KeyLookup::Ignored,
+        }
+    }
+}
+
+// Whether `prefix` starts `sequence` and is shorter
+fn is_prefix(prefix: &[KeyChord], sequence: &[KeyChord]) -> bool {
+    prefix.len() < sequence.len() && sequence.starts_with(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    fn sequence(keys: &str) -> Vec<KeyChord> {
+        parse_sequence(keys).unwrap()
+    }
+
+    fn command(name: &str) -> Command {
+        Command::try_from(name).ok().unwrap()
+    }
+
+    fn bound_to(keymap: &Keymap, keys: &str, name: &str) -> bool {
+        keymap.bindings.get(&sequence(keys)) == Some(&command(name))
+    }
+
+    fn with_defaults(name: &str) -> usize {
+        Keymap::default()
+            .bindings
+            .values()
+            .filter(|bound| **bound == command(name))
+            .count()
+    }
+
+    #[test]
+    fn parses_modifiers_in_any_case_and_order() {
+        let ctrl_alt_x = chord(
+            KeyCode::Char('x'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        );
+        assert_eq!(sequence("ctrl-alt-x"), [ctrl_alt_x]);
+        assert_eq!(sequence("Alt-Ctrl-X"), [ctrl_alt_x]);
+        assert_eq!(
+            sequence("ctrl--"),
+            [chord(KeyCode::Char('-'), KeyModifiers::CONTROL)]
+        );
+    }
+
+    #[test]
+    fn shift_is_part_of_the_character() {
+        let upper_a = chord(KeyCode::Char('A'), KeyModifiers::NONE);
+        assert_eq!(sequence("shift-a"), [upper_a]);
+        assert_eq!(chord(KeyCode::Char('a'), KeyModifiers::SHIFT), upper_a);
+        assert_eq!(
+            chord(KeyCode::BackTab, KeyModifiers::SHIFT),
+            chord(KeyCode::Tab, KeyModifiers::SHIFT)
+        );
+        assert_eq!(sequence("A"), sequence("a"));
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        assert_eq!(
+            sequence("esc enter space f12 pagedown"),
+            [
+                chord(KeyCode::Esc, KeyModifiers::NONE),
+                chord(KeyCode::Enter, KeyModifiers::NONE),
+                chord(KeyCode::Char(' '), KeyModifiers::NONE),
+                chord(KeyCode::F(12), KeyModifiers::NONE),
+                chord(KeyCode::PageDown, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_sequences_separated_by_spaces() {
+        assert_eq!(
+            sequence("  ctrl-k   ctrl-c "),
+            [
+                chord(KeyCode::Char('k'), KeyModifiers::CONTROL),
+                chord(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(parse_sequence("").is_err());
+        assert!(parse_sequence("ctrl-").is_err());
+        assert!(parse_sequence("f13").is_err());
+        assert!(parse_sequence("hyper-x").is_err());
+        assert!(parse_sequence("ctrl-k nope").is_err());
+    }
+
+    #[test]
+    fn describes_sequences_the_way_they_parse() {
+        for keys in [
+            "Ctrl-K Ctrl-C",
+            "Alt-Shift-Tab",
+            "Shift-A",
+            "Ctrl-Space",
+            "F5",
+            "Ctrl--",
+        ] {
+            assert_eq!(describe(&sequence(keys)), keys);
+        }
+    }
+
+    #[test]
+    fn user_bindings_replace_defaults_quietly() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply(
+            r#"
+            "ctrl-s" = "quit"
+            "ctrl-q" = "none"
+            "alt-x" = "cut"
+            "#,
+        );
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert!(bound_to(&keymap, "ctrl-s", "quit"));
+        assert!(!keymap.bindings.contains_key(&sequence("ctrl-q")));
+        assert!(bound_to(&keymap, "alt-x", "cut"));
+        assert!(bound_to(&keymap, "ctrl-x", "cut"));
+        // one taken away, one added
+        assert_eq!(keymap.bindings.len(), Keymap::default().bindings.len());
+    }
+
+    #[test]
+    fn turning_a_default_key_into_a_prefix_replaces_it_quietly() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply(r#""ctrl-x ctrl-s" = "save""#);
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert!(bound_to(&keymap, "ctrl-x ctrl-s", "save"));
+        assert!(!keymap.bindings.contains_key(&sequence("ctrl-x")));
+        assert_eq!(
+            keymap
+                .bindings
+                .values()
+                .filter(|bound| **bound == command("cut"))
+                .count(),
+            with_defaults("cut").saturating_sub(1)
+        );
+    }
+
+    #[test]
+    fn hiding_a_longer_sequence_is_reported() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.apply(r#""ctrl-k ctrl-c" = "copy""#).is_empty());
+
+        // as if the sequence was a default, which a single key now hides
+        let problems = keymap.apply(r#""ctrl-k" = "cut""#);
+        assert_eq!(
+            problems,
+            ["\"ctrl-k\" hides the default binding of Ctrl-K Ctrl-C"]
+        );
+        assert!(bound_to(&keymap, "ctrl-k", "cut"));
+        assert!(!keymap.bindings.contains_key(&sequence("ctrl-k ctrl-c")));
+    }
+
+    #[test]
+    fn conflicts_within_the_file_are_reported() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply(
+            r#"
+            "ctrl-k ctrl-c" = "copy"
+            "Ctrl-K Ctrl-C" = "cut"
+            "ctrl-k" = "paste"
+            "ctrl-k ctrl-c ctrl-v" = "paste"
+            "#,
+        );
+
+        assert_eq!(
+            problems,
+            [
+                "\"ctrl-k ctrl-c\" and \"Ctrl-K Ctrl-C\" are the same keys",
+                "\"ctrl-k ctrl-c\" and \"ctrl-k\" conflict, one starts the other",
+                "\"ctrl-k ctrl-c\" and \"ctrl-k ctrl-c ctrl-v\" conflict, one starts the other",
+            ]
+        );
+        // the first one wins
+        assert!(bound_to(&keymap, "ctrl-k ctrl-c", "copy"));
+    }
+
+    #[test]
+    fn bad_bindings_are_reported_and_left_out() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply(
+            r#"
+            "ctrl-s" = "sav"
+            "ctrl-q" = 1
+            "hyper-x" = "copy"
+            "#,
+        );
+
+        assert_eq!(
+            problems,
+            [
+                "\"ctrl-s\": unknown command \"sav\"",
+                "\"ctrl-q\": expected the name of a command",
+                "\"hyper-x\": unknown key \"hyper-x\"",
+            ]
+        );
+        assert!(bound_to(&keymap, "ctrl-s", "save"));
+        assert!(bound_to(&keymap, "ctrl-q", "quit"));
+    }
+
+    #[test]
+    fn sequences_wait_for_their_last_key() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.apply(r#""ctrl-x ctrl-s" = "save""#).is_empty());
+        let press = |keymap: &mut Keymap, character| {
+            keymap.lookup(KeyEvent::new(
+                KeyCode::Char(character),
+                KeyModifiers::CONTROL,
+            ))
+        };
+
+        assert!(matches!(press(&mut keymap, 'x'), KeyLookup::Pending(_)));
+        assert!(
+            matches!(press(&mut keymap, 's'), KeyLookup::Command(bound) if bound == command("save"))
+        );
+        assert!(matches!(press(&mut keymap, 'x'), KeyLookup::Pending(_)));
+        assert!(matches!(press(&mut keymap, 'q'), KeyLookup::Unbound(keys) if keys.len() == 2));
+        assert!(
+            matches!(press(&mut keymap, 'q'), KeyLookup::Command(bound) if bound == command("quit"))
+        );
+    }
+}
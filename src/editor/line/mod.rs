@@ -1,255 +1,438 @@
-use super::{AnnotatedString, Annotation};
-use crate::prelude::*;
-use graphemewidth::GraphemeWidth;
-use std::{
-    // cmp::min,
-    fmt::{self, Display},
-    ops::{Deref, Range},
-};
-use textfragment::TextFragment;
-use unicode_segmentation::UnicodeSegmentation;
-use unicode_width::UnicodeWidthStr;
-
-mod graphemewidth;
-mod textfragment;
-
-// type GraphemeIdx = usize;
-// type ByteIdx = usize;
-// type ColIdx = usize;
-
-#[derive(Default, Clone)]
-pub struct Line {
-    fragments: Vec<TextFragment>,
-    string: String,
-}
-
-impl Line {
-    pub fn from(line_str: &str) -> Self {
-        let fragments: Vec<TextFragment> = Self::str_to_fragments(line_str);
-
-        Self {
-            fragments,
-            string: String::from(line_str),
-        }
-    }
-
-    fn str_to_fragments(line_str: &str) -> Vec<TextFragment> {
-        line_str
-            .grapheme_indices(true)
-            .map(|(byte_idx, grapheme)| {
-                let (replacement, rendered_width) = Self::get_replacement_character(grapheme)
-                    .map_or_else(
-                        || {
-                            let unicode_width: usize = grapheme.width();
-                            let rendered_width: GraphemeWidth = match unicode_width {
-                                0 | 1 => GraphemeWidth::Half,
-                                _ => GraphemeWidth::Full,
-                            };
-                            (None, rendered_width)
-                        },
-                        |replacement| (Some(replacement), GraphemeWidth::Half),
-                    );
-
-                TextFragment {
-                    grapheme: grapheme.to_string(),
-                    rendered_width,
-                    replacement,
-                    start: byte_idx,
-                }
-            })
-            .collect()
-    }
-
-    fn rebuild_fragments(&mut self) {
-        self.fragments = Self::str_to_fragments(&self.string);
-    }
-
-    fn get_replacement_character(for_str: &str) -> Option<char> {
-        let width: usize = for_str.width();
-
-        match for_str {
-            " " => None,
-            "\t" => Some(' '),
-            _ if width > 0 && for_str.trim().is_empty() => Some('␣'),
-            _ if width == 0 => {
-                let mut chars: std::str::Chars = for_str.chars();
-                if let Some(ch) = chars.next() {
-                    if ch.is_control() && chars.next().is_none() {
-                        return Some('▯');
-                    }
-                }
-                Some('·')
-            }
-            _ => None,
-        }
-    }
-
-    pub fn get_visible_graphemes(&self, range: Range<usize>) -> String {
-        self.get_annotated_visible_substr(range, None).to_string()
-    }
-
-    pub fn get_annotated_visible_substr(
-        &self,
-        range: Range<ColIdx>,
-        annotations: Option<&Vec<Annotation>>,
-    ) -> AnnotatedString {
-        if range.start >= range.end {
-            return AnnotatedString::default();
-        }
-        // Create a new annotated string
-        let mut result = AnnotatedString::from(&self.string);
-
-        if let Some(annotations) = annotations {
-            for annotation in annotations {
-                result.add_annotation(annotation.annotation_type, annotation.start, annotation.end);
-            }
-        }
-
-        // Insert replacement characters and truncate if needed
-        // and we iterate fragments from rightmost column first
-        let mut fragment_start = self.width();
-
-        for fragment in self.fragments.iter().rev() {
-            let fragment_end = fragment_start;
-
-            fragment_start = fragment_start.saturating_sub(fragment.rendered_width.into());
-
-            if fragment_start > range.end {
-                continue; // This fragment is not visible
-            }
-
-            if fragment_start < range.end && fragment_end > range.end {
-                // the fragment is cut into two parts,
-                // the left part is visible,
-                // and the right part is not visible
-                result.replace(fragment.start, self.string.len(), "...");
-                continue;
-            } else if fragment_start == range.end {
-                result.truncate_right_from(fragment.start);
-                continue;
-            }
-
-            if fragment_end <= range.start {
-                // the fragment ends at the start of the range
-                result.truncate_left_until(fragment.start.saturating_add(fragment.grapheme.len()));
-                break; // break out of the loop since all fragments remained are not visible
-            } else if fragment_start < range.start && fragment_end > range.start {
-                // the fragment is cut into two parts,
-                // the right part is visible,
-                // and the left part is not visible
-                result.replace(
-                    0,
-                    fragment.start.saturating_add(fragment.grapheme.len()),
-                    "...",
-                );
-                break; // break out of the loop since all fragments remained are not visible
-            }
-
-            if fragment_start >= range.start && fragment_end <= range.end {
-                // the fragment is completely visible
-                if let Some(replcement) = fragment.replacement {
-                    let start = fragment.start;
-                    let end = start.saturating_add(fragment.grapheme.len());
-
-                    result.replace(start, end, &replcement.to_string());
-                }
-            }
-        }
-
-        result
-    }
-
-    pub fn grapheme_count(&self) -> usize {
-        self.fragments.len()
-    }
-
-    pub fn width_until(&self, grapheme_index: usize) -> ColIdx {
-        self.fragments
-            .iter()
-            .take(grapheme_index)
-            .map(|fragment| match fragment.rendered_width {
-                GraphemeWidth::Half => 1,
-                GraphemeWidth::Full => 2,
-            })
-            .sum()
-    }
-
-    pub fn width(&self) -> ColIdx {
-        self.width_until(self.grapheme_count())
-    }
-
-    pub fn insert_char(&mut self, character: char, at: usize) {
-        if let Some(fragment) = self.fragments.get_mut(at) {
-            self.string.insert(fragment.start, character);
-        } else {
-            self.string.push(character);
-        }
-
-        self.rebuild_fragments();
-    }
-
-    pub fn delete_char(&mut self, at: usize) {
-        if let Some(fragment) = self.fragments.get(at) {
-            let start = fragment.start;
-            let end = fragment.start.saturating_add(fragment.grapheme.len());
-            self.string.drain(start..end);
-            self.rebuild_fragments();
-        }
-    }
-
-    pub fn append(&mut self, other: &Self) {
-        self.string.push_str(&other.to_string());
-        self.rebuild_fragments();
-    }
-
-    pub fn split(&mut self, at: usize) -> Self {
-        if let Some(fragment) = self.fragments.get(at) {
-            let remainder = self.string.split_off(fragment.start);
-
-            self.rebuild_fragments();
-
-            Self::from(&remainder)
-        } else {
-            Self::default()
-        }
-    }
-
-    fn byte_idx_to_grapheme_idx(&self, byte_idx: ByteIdx) -> Option<GraphemeIdx> {
-        if byte_idx > self.string.len() {
-            return None;
-        }
-
-        self.fragments
-            .iter()
-            .position(|fragment| fragment.start >= byte_idx)
-    }
-
-    pub fn search(&self, query: &str) -> Option<Vec<ByteIdx>> {
-        let result: Vec<ByteIdx> = self
-            .string
-            .match_indices(query)
-            .map(|(byte_idx, _)| self.byte_idx_to_grapheme_idx(byte_idx))
-            .map(|x| x.unwrap())
-            .collect();
-
-        if result.is_empty() {
-            None
-        } else {
-            Some(result)
-        }
-    }
-}
-
-impl Display for Line {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.string)
-    }
-}
-
-impl Deref for Line {
-    type Target = str;
-
-    fn deref(&self) -> &Self::Target {
-        &self.string
-    }
-}
+use super::{AnnotatedString, Annotation, AnnotationType};
+use crate::prelude::*;
+use graphemewidth::GraphemeWidth;
+use regex::Regex;
+use std::{
+    cell::OnceCell,
+    // cmp::min,
+    fmt::{self, Display},
+    ops::{Deref, Range},
+};
+use textfragment::TextFragment;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+mod graphemewidth;
+mod textfragment;
+
+// type GraphemeIdx = usize;
+// type ByteIdx = usize;
+// type ColIdx = usize;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WordKind {
+    Word,
+    Punctuation,
+    Whitespace,
+}
+
+// How tabs are laid out and drawn
+#[derive(Clone, Copy, Debug)]
+pub struct TabStops {
+    pub width: ColIdx, // columns from one tab stop to the next
+    pub show: bool,    // draw tabs as an arrow rather than blank
+}
+
+// Outside of documents, e.g. in the command bar, the caret is placed by grapheme, so tabs take a single column there
+impl Default for TabStops {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            show: false,
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Line {
+    fragments: OnceCell<Vec<TextFragment>>, // built on first use, most lines never need them
+    string: String,
+    tab_stops: TabStops,
+}
+
+impl Line {
+    pub fn from(line_str: &str) -> Self {
+        Self::with_tab_stops(line_str, TabStops::default())
+    }
+
+    pub fn with_tab_stops(line_str: &str, tab_stops: TabStops) -> Self {
+        Self {
+            fragments: OnceCell::new(),
+            string: String::from(line_str),
+            tab_stops,
+        }
+    }
+
+    fn fragments(&self) -> &Vec<TextFragment> {
+        self.fragments
+            .get_or_init(|| Self::str_to_fragments(&self.string, self.tab_stops))
+    }
+
+    // A tab reaches up to the next tab stop, so its width depends on the width of everything before it
+    fn str_to_fragments(line_str: &str, tab_stops: TabStops) -> Vec<TextFragment> {
+        let tab_width = tab_stops.width.max(1);
+        let mut col: ColIdx = 0;
+
+        line_str
+            .grapheme_indices(true)
+            .map(|(byte_idx, grapheme)| {
+                if grapheme == "\t" {
+                    let rendered_width =
+                        GraphemeWidth::Tab(tab_width.saturating_sub(col % tab_width));
+                    col = rendered_width.saturating_add(col);
+                    return TextFragment {
+                        grapheme: grapheme.to_string(),
+                        rendered_width,
+                        replacement: Some(if tab_stops.show { '→' } else { ' ' }),
+                        start: byte_idx,
+                    };
+                }
+
+                let (replacement, rendered_width) = Self::get_replacement_character(grapheme)
+                    .map_or_else(
+                        || {
+                            let unicode_width: usize = grapheme.width();
+                            let rendered_width: GraphemeWidth = match unicode_width {
+                                0 | 1 => GraphemeWidth::Half,
+                                _ => GraphemeWidth::Full,
+                            };
+                            (None, rendered_width)
+                        },
+                        |replacement| (Some(replacement), GraphemeWidth::Half),
+                    );
+                col = rendered_width.saturating_add(col);
+
+                TextFragment {
+                    grapheme: grapheme.to_string(),
+                    rendered_width,
+                    replacement,
+                    start: byte_idx,
+                }
+            })
+            .collect()
+    }
+
+    fn reset_fragments(&mut self) {
+        self.fragments = OnceCell::new();
+    }
+
+    fn get_replacement_character(for_str: &str) -> Option<char> {
+        let width: usize = for_str.width();
+
+        match for_str {
+            " " => None,
+            _ if width > 0 && for_str.trim().is_empty() => Some('␣'),
+            _ if width == 0 => {
+                let mut chars: std::str::Chars = for_str.chars();
+                if let Some(ch) = chars.next() {
+                    if ch.is_control() && chars.next().is_none() {
+                        return Some('▯');
+                    }
+                }
+                Some('·')
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_visible_graphemes(&self, range: Range<usize>) -> String {
+        self.get_annotated_visible_substr(range, None).to_string()
+    }
+
+    pub fn get_annotated_visible_substr(
+        &self,
+        range: Range<ColIdx>,
+        annotations: Option<&Vec<Annotation>>,
+    ) -> AnnotatedString {
+        if range.start >= range.end {
+            return AnnotatedString::default();
+        }
+        // Create a new annotated string
+        let mut result = AnnotatedString::from(&self.string);
+
+        // added first, so that any other annotation on a tab takes precedence
+        if self.tab_stops.show {
+            for fragment in self.fragments().iter().filter(|fragment| fragment.is_tab()) {
+                result.add_annotation(
+                    AnnotationType::Tab,
+                    fragment.start,
+                    fragment.start.saturating_add(fragment.grapheme.len()),
+                );
+            }
+        }
+
+        if let Some(annotations) = annotations {
+            for annotation in annotations {
+                result.add_annotation(annotation.annotation_type, annotation.start, annotation.end);
+            }
+        }
+
+        // Insert replacement characters and truncate if needed
+        // and we iterate fragments from rightmost column first
+        let mut fragment_start = self.width();
+
+        for fragment in self.fragments().iter().rev() {
+            let fragment_end = fragment_start;
+
+            fragment_start = fragment_start.saturating_sub(fragment.rendered_width.into());
+
+            if fragment_start > range.end {
+                continue; // This fragment is not visible
+            }
+
+            if fragment_start < range.end && fragment_end > range.end {
+                // the fragment is cut into two parts,
+                // the left part is visible,
+                // and the right part is not visible
+                let cut = if fragment.is_tab() {
+                    " ".repeat(range.end.saturating_sub(fragment_start))
+                } else {
+                    "...".to_string()
+                };
+                result.replace(fragment.start, self.string.len(), &cut);
+                continue;
+            } else if fragment_start == range.end {
+                result.truncate_right_from(fragment.start);
+                continue;
+            }
+
+            if fragment_end <= range.start {
+                // the fragment ends at the start of the range
+                result.truncate_left_until(fragment.start.saturating_add(fragment.grapheme.len()));
+                break; // break out of the loop since all fragments remained are not visible
+            } else if fragment_start < range.start && fragment_end > range.start {
+                // the fragment is cut into two parts,
+                // the right part is visible,
+                // and the left part is not visible
+                let cut = if fragment.is_tab() {
+                    " ".repeat(fragment_end.saturating_sub(range.start))
+                } else {
+                    "...".to_string()
+                };
+                result.replace(
+                    0,
+                    fragment.start.saturating_add(fragment.grapheme.len()),
+                    &cut,
+                );
+                break; // break out of the loop since all fragments remained are not visible
+            }
+
+            if fragment_start >= range.start && fragment_end <= range.end {
+                // the fragment is completely visible
+                // a tab's replacement is filled up with blanks to its width
+                if let Some(replcement) = fragment.replacement {
+                    let start = fragment.start;
+                    let end = start.saturating_add(fragment.grapheme.len());
+                    let width = usize::from(fragment.rendered_width);
+
+                    result.replace(start, end, &format!("{replcement:<width$}"));
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn grapheme_count(&self) -> usize {
+        self.fragments().len()
+    }
+
+    // Splits the line into rows no wider than `width` and returns the grapheme each row starts at.
+    // Rows end after whitespace where possible, so words stay whole. Graphemes are never split,
+    // a full width one that doesn't fit anymore moves to the next row. A line that fills its last row
+    // completely gets an empty row after it, so that the caret at its end has somewhere to go.
+    pub fn wrap(&self, width: ColIdx) -> Vec<GraphemeIdx> {
+        let mut row_starts = vec![0];
+        if width == 0 {
+            return row_starts;
+        }
+
+        let fragments = self.fragments();
+        let mut row_start = 0;
+        let mut row_width: ColIdx = 0;
+        let mut break_after_whitespace: Option<GraphemeIdx> = None;
+
+        for (idx, fragment) in fragments.iter().enumerate() {
+            let fragment_width = usize::from(fragment.rendered_width);
+
+            // breaking after whitespace can still leave too little room for a full width grapheme, so check again
+            while row_width.saturating_add(fragment_width) > width && idx > row_start {
+                let is_whitespace = fragment.grapheme.trim().is_empty();
+                row_start = match break_after_whitespace {
+                    Some(after_whitespace) if !is_whitespace => after_whitespace,
+                    _ => idx,
+                };
+                row_starts.push(row_start);
+                row_width = fragments[row_start..idx]
+                    .iter()
+                    .map(|fragment| usize::from(fragment.rendered_width))
+                    .sum();
+                break_after_whitespace = None;
+            }
+
+            row_width = row_width.saturating_add(fragment_width);
+            if fragment.grapheme.trim().is_empty() {
+                break_after_whitespace = Some(idx.saturating_add(1));
+            }
+        }
+
+        if row_width >= width {
+            row_starts.push(fragments.len());
+        }
+
+        row_starts
+    }
+
+    // The grapheme `col` columns to the right of the start of `from`, but not past `to`
+    pub fn grapheme_at_col(&self, from: GraphemeIdx, to: GraphemeIdx, col: ColIdx) -> GraphemeIdx {
+        let mut width: ColIdx = 0;
+
+        for (idx, fragment) in self.fragments().iter().enumerate().take(to).skip(from) {
+            width = fragment.rendered_width.saturating_add(width);
+            if width > col {
+                return idx;
+            }
+        }
+
+        to
+    }
+
+    // Where moving a word to the left of `from` ends up: the start of the word, or run of punctuation,
+    // before it. Whitespace is skipped over.
+    pub fn word_start_before(&self, from: GraphemeIdx) -> GraphemeIdx {
+        self.word_runs()
+            .into_iter()
+            .rev()
+            .find(|(range, kind)| *kind != WordKind::Whitespace && range.start < from)
+            .map_or(0, |(range, _)| range.start)
+    }
+
+    // Where moving a word to the right of `from` ends up: the end of the word, or run of punctuation,
+    // after it. Whitespace is skipped over.
+    pub fn word_end_after(&self, from: GraphemeIdx) -> GraphemeIdx {
+        self.word_runs()
+            .into_iter()
+            .find(|(range, kind)| *kind != WordKind::Whitespace && range.end > from)
+            .map_or_else(|| self.grapheme_count(), |(range, _)| range.end)
+    }
+
+    // Splits the line at Unicode word boundaries. Words stay separate, but punctuation like `->` or `::`
+    // is split into single characters there, so those are joined into runs again.
+    fn word_runs(&self) -> Vec<(Range<GraphemeIdx>, WordKind)> {
+        let mut to_grapheme_idx = self.grapheme_idx_walker();
+        let mut runs: Vec<(Range<GraphemeIdx>, WordKind)> = Vec::new();
+
+        for (byte_idx, segment) in self.string.split_word_bound_indices() {
+            let kind = if segment.chars().all(char::is_whitespace) {
+                WordKind::Whitespace
+            } else if segment.chars().any(char::is_alphanumeric) {
+                WordKind::Word
+            } else {
+                WordKind::Punctuation
+            };
+            let start = to_grapheme_idx(byte_idx);
+            let end = to_grapheme_idx(byte_idx.saturating_add(segment.len()));
+
+            match runs.last_mut() {
+                Some((range, last_kind)) if *last_kind == kind && kind != WordKind::Word => {
+                    range.end = end;
+                }
+                _ => runs.push((start..end, kind)),
+            }
+        }
+
+        runs
+    }
+
+    pub fn width_until(&self, grapheme_index: usize) -> ColIdx {
+        self.fragments()
+            .iter()
+            .take(grapheme_index)
+            .map(|fragment| usize::from(fragment.rendered_width))
+            .sum()
+    }
+
+    pub fn width(&self) -> ColIdx {
+        self.width_until(self.grapheme_count())
+    }
+
+    pub fn insert_char(&mut self, character: char, at: usize) {
+        let byte_idx = self.grapheme_idx_to_byte_idx(at);
+        self.string.insert(byte_idx, character);
+        self.reset_fragments();
+    }
+
+    pub fn delete_char(&mut self, at: usize) {
+        if let Some(fragment) = self.fragments().get(at) {
+            let start = fragment.start;
+            let end = fragment.start.saturating_add(fragment.grapheme.len());
+            self.string.drain(start..end);
+            self.reset_fragments();
+        }
+    }
+
+    pub fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+        self.fragments()
+            .get(grapheme_idx)
+            .map_or(self.string.len(), |fragment| fragment.start)
+    }
+
+    pub fn byte_idx_to_grapheme_idx(&self, byte_idx: ByteIdx) -> Option<GraphemeIdx> {
+        if byte_idx > self.string.len() {
+            return None;
+        }
+
+        self.fragments()
+            .iter()
+            .position(|fragment| fragment.start >= byte_idx)
+    }
+
+    // Like `byte_idx_to_grapheme_idx`, for byte indices which never decrease, so the fragments are walked only once.
+    // A byte index past the last grapheme gives the grapheme count.
+    pub fn grapheme_idx_walker(&self) -> impl FnMut(ByteIdx) -> GraphemeIdx + '_ {
+        let fragments = self.fragments();
+        let mut grapheme_idx = 0;
+        move |byte_idx| {
+            while fragments
+                .get(grapheme_idx)
+                .is_some_and(|fragment| fragment.start < byte_idx)
+            {
+                grapheme_idx = grapheme_idx.saturating_add(1);
+            }
+            grapheme_idx
+        }
+    }
+
+    // Finds the matches of the pattern, as grapheme ranges. Empty matches are left out, there's nothing to show for them.
+    pub fn search(&self, pattern: &Regex) -> Option<Vec<Range<GraphemeIdx>>> {
+        let mut to_grapheme_idx = self.grapheme_idx_walker();
+        let result: Vec<Range<GraphemeIdx>> = pattern
+            .find_iter(&self.string)
+            .filter_map(|found| {
+                let start = to_grapheme_idx(found.start());
+                let end = to_grapheme_idx(found.end());
+                (start < end).then_some(start..end)
+            })
+            .collect();
+
+        if result.is_empty() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.string)
+    }
+}
+
+impl Deref for Line {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
@@ -1,10 +1,16 @@
-use super::GraphemeWidth;
-use crate::prelude::*;
-
-#[derive(Clone, Debug)]
-pub struct TextFragment {
-    pub grapheme: String,
-    pub rendered_width: GraphemeWidth,
-    pub replacement: Option<char>,
-    pub start: ByteIdx,
-}
+use super::GraphemeWidth;
+use crate::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct TextFragment {
+    pub grapheme: String,
+    pub rendered_width: GraphemeWidth,
+    pub replacement: Option<char>,
+    pub start: ByteIdx,
+}
+
+impl TextFragment {
+    pub fn is_tab(&self) -> bool {
+        self.grapheme == "\t"
+    }
+}
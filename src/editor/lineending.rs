@@ -0,0 +1,55 @@
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    // Detects the style most line breaks in the text use, LF if there are none
+    pub fn detect(text: &str) -> Self {
+        let (lf, crlf) = Self::count(text);
+        if crlf > lf {
+            Self::Crlf
+        } else {
+            Self::Lf
+        }
+    }
+
+    // Whether the text uses both styles, converting it to one changes some of the lines
+    pub fn is_mixed(text: &str) -> bool {
+        let (lf, crlf) = Self::count(text);
+        lf > 0 && crlf > 0
+    }
+
+    // How many line breaks are LF on their own and how many are CRLF
+    fn count(text: &str) -> (usize, usize) {
+        let crlf = text.matches("\r\n").count();
+        (text.matches('\n').count().saturating_sub(crlf), crlf)
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Lf => Self::Crlf,
+            Self::Crlf => Self::Lf,
+        }
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Lf => write!(f, "LF"),
+            Self::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
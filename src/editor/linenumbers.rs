@@ -0,0 +1,64 @@
+use crate::prelude::*;
+use std::{
+    fmt::{Display, Formatter, Result},
+    str::FromStr,
+};
+
+// How the gutter numbers the lines
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineNumbers {
+    #[default]
+    Off,
+    Absolute,
+    Relative, // the distance to the caret's line
+    Hybrid,   // relative, except for the caret's line, which shows its own number
+}
+
+impl LineNumbers {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Absolute,
+            Self::Absolute => Self::Relative,
+            Self::Relative => Self::Hybrid,
+            Self::Hybrid => Self::Off,
+        }
+    }
+
+    // The number shown for the line, None for no number at all
+    pub const fn number(self, line_idx: LineIdx, caret_line_idx: LineIdx) -> Option<usize> {
+        match self {
+            Self::Off => None,
+            Self::Absolute => Some(line_idx.saturating_add(1)),
+            Self::Relative => Some(line_idx.abs_diff(caret_line_idx)),
+            Self::Hybrid if line_idx == caret_line_idx => Some(line_idx.saturating_add(1)),
+            Self::Hybrid => Some(line_idx.abs_diff(caret_line_idx)),
+        }
+    }
+}
+
+impl Display for LineNumbers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Absolute => write!(f, "absolute"),
+            Self::Relative => write!(f, "relative"),
+            Self::Hybrid => write!(f, "hybrid"),
+        }
+    }
+}
+
+impl FromStr for LineNumbers {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "off" => Ok(Self::Off),
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => Err(format!(
+                "expected \"off\", \"absolute\", \"relative\" or \"hybrid\", not \"{value}\""
+            )),
+        }
+    }
+}
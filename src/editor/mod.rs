@@ -1,365 +1,1320 @@
-use self::command::{
-    Command::{self, Edit, Move, System},
-    Edit::InsertNewline,
-    Move::{Down, Up},
-    System::{Dismiss, Quit, Resize, Save, Search},
-};
-use crate::prelude::*;
-use annotatedstring::AnnotatedString;
-use annotation::Annotation;
-pub use annotationtype::AnnotationType;
-use crossterm::event::{read, Event, KeyEvent, KeyEventKind};
-use documentstatus::DocumentStatus;
-use filetype::FileType;
-use line::Line;
-use std::{
-    env,
-    io::Error,
-    panic::{set_hook, take_hook},
-};
-use terminal::Terminal;
-use uicomponents::{CommandBar, MessageBar, StatusBar, UIComponent, View};
-
-mod annotatedstring;
-mod annotation;
-pub mod annotationtype;
-mod command;
-mod documentstatus;
-mod filetype;
-mod line;
-mod terminal;
-mod uicomponents;
-
-const QUIT_TIMES: u8 = 3;
-
-#[derive(Eq, PartialEq, Default)]
-enum PromptType {
-    #[default]
-    None,
-    Search,
-    Save,
-}
-
-impl PromptType {
-    fn is_none(&self) -> bool {
-        *self == Self::None
-    }
-}
-
-#[derive(Default)]
-pub struct Editor {
-    should_exit: bool,
-    view: View,
-    status_bar: StatusBar,
-    message_bar: MessageBar,
-    command_bar: CommandBar,
-    prompt_type: PromptType,
-    terminal_size: Size,
-    title: String,
-    quit_times: u8,
-}
-
-impl Editor {
-    // SECTION: struct lifecycle
-
-    pub fn new() -> Result<Self, Error> {
-        let current_hook = take_hook();
-        set_hook(Box::new(move |panic_info| {
-            let _ = Terminal::terminate();
-            current_hook(panic_info);
-        }));
-
-        Terminal::initialize()?;
-
-        let args: Vec<String> = env::args().collect();
-
-        let mut editor = Self::default();
-        let size = Terminal::size().unwrap_or_default();
-
-        editor.handle_resize_command(size);
-        editor.update_message("HELP: Ctrl-F = find | Ctrl-S = save | Ctrl-Q = quit");
-
-        if let Some(file_name) = args.get(1) {
-            if editor.view.load_file(file_name).is_err() {
-                editor.update_message(&format!("ERR: Could not open file: {file_name}"));
-            }
-        }
-
-        editor.refrest_status();
-
-        Ok(editor)
-    }
-
-    // END SECTION
-
-    // SECTION: event loop
-
-    pub fn run(&mut self) {
-        loop {
-            self.refresh_screen();
-
-            if self.should_exit {
-                break;
-            }
-
-            match read() {
-                Ok(event) => self.evaluate_event(event),
-                Err(err) => {
-                    #[cfg(debug_assertions)]
-                    {
-                        panic!("Could not read event: {err:?}");
-                    }
-                }
-            }
-
-            self.refrest_status();
-        }
-    }
-
-    fn refresh_screen(&mut self) {
-        if self.terminal_size.height == 0 || self.terminal_size.width == 0 {
-            return;
-        }
-        let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
-        let _ = Terminal::hide_caret();
-        if self.in_prompt() {
-            self.command_bar.render(bottom_bar_row);
-        } else {
-            self.message_bar.render(bottom_bar_row);
-        }
-
-        if self.terminal_size.height > 1 {
-            self.status_bar
-                .render(self.terminal_size.height.saturating_sub(2));
-        }
-
-        if self.terminal_size.height > 2 {
-            self.view.render(0);
-        }
-
-        let new_caret_pos = if self.in_prompt() {
-            Position {
-                row: bottom_bar_row,
-                col: self.command_bar.caret_position_col(),
-            }
-        } else {
-            self.view.caret_position()
-        };
-
-        let _ = Terminal::move_caret_to(new_caret_pos);
-        let _ = Terminal::show_caret();
-        let _ = Terminal::execute();
-    }
-
-    fn refrest_status(&mut self) {
-        let status: DocumentStatus = self.view.get_status();
-        let title: String = format!("{} - {NAME}", status.file_name);
-
-        self.status_bar.update_status(status);
-
-        if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
-            self.title = title;
-        }
-    }
-
-    fn evaluate_event(&mut self, event: Event) {
-        let should_process = match &event {
-            Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
-            Event::Resize(_, _) => true,
-            _ => false,
-        };
-
-        if should_process {
-            if let Ok(command) = Command::try_from(event) {
-                self.process_command(command);
-            }
-        }
-    }
-
-    // ENDSECTION
-
-    // SECTION: commands handling
-
-    fn process_command(&mut self, command: Command) {
-        if let System(Resize(size)) = command {
-            self.handle_resize_command(size);
-            return;
-        }
-        match self.prompt_type {
-            PromptType::Search => self.process_command_during_search(command),
-            PromptType::Save => self.process_command_during_save(command),
-            PromptType::None => self.process_command_no_prompt(command),
-        }
-    }
-
-    fn process_command_no_prompt(&mut self, command: Command) {
-        if matches!(command, System(Quit)) {
-            self.handle_quit_command();
-            return;
-        }
-
-        self.reset_quit_times();
-
-        match command {
-            System(Quit | Resize(_) | Dismiss) => {}
-            System(Save) => self.handle_save_command(),
-            System(Search) => self.set_prompt(PromptType::Search),
-            Edit(edit_command) => self.view.handle_edit_command(edit_command),
-            Move(move_command) => self.view.handle_move_command(move_command),
-        }
-    }
-
-    // SECTION: save command and prompt handling
-
-    fn handle_save_command(&mut self) {
-        if self.view.is_file_loaded() {
-            self.save_file(None);
-        } else {
-            self.set_prompt(PromptType::Save);
-        }
-    }
-
-    fn process_command_during_save(&mut self, command: Command) {
-        match command {
-            System(Quit | Resize(_) | Search | Save) => {}
-            System(Dismiss) => {
-                self.set_prompt(PromptType::None);
-                self.update_message("Save aborted.");
-            }
-            Edit(InsertNewline) => {
-                let file_name = self.command_bar.value();
-                self.save_file(Some(&file_name));
-                self.set_prompt(PromptType::None);
-            }
-            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
-            Move(move_command) => self.command_bar.handle_move_command(move_command),
-        }
-    }
-
-    fn save_file(&mut self, file_name: Option<&str>) {
-        let result = if let Some(name) = file_name {
-            self.view.save_as(name)
-        } else {
-            self.view.save_file()
-        };
-
-        if result.is_ok() {
-            self.message_bar.update_message("File saved successfully");
-        } else {
-            self.message_bar.update_message("Could not save file");
-        }
-    }
-
-    // END SECTION
-
-    // SECTION: search command and prompt handling
-
-    fn process_command_during_search(&mut self, command: Command) {
-        match command {
-            System(Quit | Resize(_) | Search | Save) => {}
-            System(Dismiss) => {
-                self.set_prompt(PromptType::None);
-                self.view.dismiss_search();
-            }
-            Edit(InsertNewline) => {
-                self.set_prompt(PromptType::None);
-                self.view.exit_search();
-            }
-            Edit(edit_command) => {
-                self.command_bar.handle_edit_command(edit_command);
-                let query = self.command_bar.value();
-                self.view.search(&query);
-            }
-            Move(Up) => self.view.prev_search_result(),
-            Move(Down) => self.view.next_search_result(),
-            Move(move_command) => self.command_bar.handle_move_command(move_command),
-        }
-    }
-
-    // END SECTION
-
-    // SECTION: quit command handling
-
-    #[allow(clippy::arithmetic_side_effects)]
-    fn handle_quit_command(&mut self) {
-        if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
-            self.should_exit = true;
-        } else if self.view.get_status().is_modified {
-            self.update_message(&format!(
-                "WARNING! File has unsaved changes. Press Ctrl-Q {} more times to quit.",
-                QUIT_TIMES - self.quit_times - 1
-            ));
-
-            self.quit_times += 1;
-        }
-    }
-
-    fn reset_quit_times(&mut self) {
-        if self.quit_times > 0 {
-            self.quit_times = 0;
-            self.message_bar.update_message("");
-        }
-    }
-
-    // END SECTION
-
-    // SECTION: message and command bar
-
-    fn update_message(&mut self, new_message: &str) {
-        self.message_bar.update_message(new_message);
-    }
-
-    // END SECTION
-
-    // SECTION: resize command handling
-    fn handle_resize_command(&mut self, size: Size) {
-        self.terminal_size = size;
-        self.view.resize(Size {
-            height: size.height.saturating_sub(2),
-            width: size.width,
-        });
-
-        let bar_size = Size {
-            height: 1,
-            width: size.width,
-        };
-
-        self.message_bar.resize(bar_size);
-        self.status_bar.resize(bar_size);
-        self.command_bar.resize(bar_size);
-    }
-
-    // END SECTION
-
-    // SECTION: prompt handling
-    fn in_prompt(&self) -> bool {
-        !self.prompt_type.is_none()
-    }
-
-    fn set_prompt(&mut self, prompt_type: PromptType) {
-        match prompt_type {
-            PromptType::None => self.message_bar.set_needs_redraw(true),
-            PromptType::Save => self.command_bar.set_prompt("Save as: "),
-            PromptType::Search => {
-                self.command_bar
-                    .set_prompt("Search (Esc to cancel), ↑ and ↓ to navigate: ");
-                self.view.enter_search();
-            }
-        }
-
-        self.command_bar.clear_value();
-        self.prompt_type = prompt_type;
-    }
-
-    // END SECTION
-}
-
-impl Drop for Editor {
-    fn drop(&mut self) {
-        let _ = Terminal::terminate();
-
-        if self.should_exit {
-            let _ = Terminal::print("Farewell!!!\r\n");
-        }
-    }
-}
+use self::command::{
+    Clipboard::{Copy, Cut, Paste},
+    Command::{self, BracketedPaste, Clipboard, Edit, Move, Pane, System},
+    Edit::{Insert, InsertNewline},
+    Move::{Down, PageDown, PageUp, Up},
+    Pane as PaneCommand,
+    System::{
+        CloseBuffer, CycleCaseSensitivity, CycleLineNumbers, Dismiss, GoToLine, ListBuffers,
+        NextBuffer, Open, PreviousBuffer, Quit, Reload, Replace, Resize, Save, Search,
+        ToggleLineEnding, ToggleRegex, ToggleSoftWrap, ToggleWholeWord,
+    },
+};
+use crate::prelude::*;
+use annotatedstring::AnnotatedString;
+use annotation::Annotation;
+pub use annotationtype::AnnotationType;
+use config::Config;
+use crossterm::event::{poll, read, Event, KeyEvent, KeyEventKind};
+use documentstatus::DocumentStatus;
+use encoding::Encoding;
+use filetype::FileType;
+use goto::GoTo;
+use keymap::{KeyLookup, Keymap};
+use line::{Line, TabStops};
+use lineending::LineEnding;
+use linenumbers::LineNumbers;
+use prompthistory::PromptHistory;
+use replaceinfo::ReplaceInfo;
+use searchoptions::SearchOptions;
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    io::Error,
+    panic::{set_hook, take_hook},
+    path::PathBuf,
+};
+use terminal::Terminal;
+use theme::Theme;
+use uicomponents::{
+    flush_snapshots, CommandBar, Layout, MessageBar, SplitDirection, StatusBar, UIComponent, View,
+};
+
+mod annotatedstring;
+mod annotation;
+pub mod annotationtype;
+mod command;
+mod config;
+mod documentstatus;
+mod encoding;
+mod filetype;
+mod goto;
+mod keymap;
+mod line;
+mod lineending;
+mod linenumbers;
+mod prompthistory;
+mod replaceinfo;
+mod searchoptions;
+mod terminal;
+mod theme;
+mod uicomponents;
+
+#[derive(Eq, PartialEq, Default)]
+enum PromptType {
+    #[default]
+    None,
+    Search,
+    Replace,
+    ReplaceWith,
+    ConfirmReplace,
+    Save,
+    Recover,
+    ConfirmOverwrite,
+    ConfirmReload,
+    Open,
+    ConfirmClose,
+    GoToLine,
+}
+
+impl PromptType {
+    fn is_none(&self) -> bool {
+        *self == Self::None
+    }
+
+    // Prompts that take a value keep a history of what was entered into them.
+    // The search term of a replace is part of the search history.
+    const fn history_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Search | Self::Replace => Some("search"),
+            Self::ReplaceWith => Some("replace"),
+            Self::Save => Some("save"),
+            Self::Open => Some("open"),
+            Self::GoToLine => Some("goto"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Editor {
+    should_exit: bool,
+    layout: Layout,     // the panes on screen
+    buffers: Vec<View>, // every open buffer, as it was last shown
+    keep_backup: bool,
+    status_bar: StatusBar,
+    message_bar: MessageBar,
+    command_bar: CommandBar,
+    prompt_type: PromptType,
+    search_options: SearchOptions, // kept from one search to the next
+    replace_info: Option<ReplaceInfo>,
+    histories: HashMap<&'static str, PromptHistory>, // loaded when the prompt is first used
+    persist_history: bool,
+    terminal_size: Size,
+    title: String,
+    quit_times: u8,
+    clipboard: String,
+    config: Config,
+    keymap: Keymap,
+    keymap_pending: bool,       // a key sequence is shown in the message bar
+    problems: VecDeque<String>, // what's wrong with the config and keymap files, yet to be shown
+}
+
+impl Editor {
+    // SECTION: struct lifecycle
+
+    pub fn new() -> Result<Self, Error> {
+        let current_hook = take_hook();
+        set_hook(Box::new(move |panic_info| {
+            flush_snapshots();
+            let _ = Terminal::terminate();
+            current_hook(panic_info);
+        }));
+
+        Terminal::initialize()?;
+
+        let args: Vec<String> = env::args().collect();
+
+        let mut editor = Self::default();
+        let size = Terminal::size().unwrap_or_default();
+
+        editor.handle_resize_command(size);
+
+        let (config, config_problems) = Config::load();
+        let (keymap, keymap_problems) = Keymap::load();
+        Terminal::set_theme(config.theme);
+        editor.config = config;
+        editor.keymap = keymap;
+        editor.update_message(&editor.keymap.help());
+
+        editor.problems = config_problems
+            .iter()
+            .map(|problem| format!("config.toml: {problem}"))
+            .chain(
+                keymap_problems
+                    .iter()
+                    .map(|problem| format!("keys.toml: {problem}")),
+            )
+            .collect();
+        editor.show_next_problem();
+
+        editor.keep_backup =
+            editor.config.keep_backup || args.iter().skip(1).any(|arg| arg == "--backup");
+        let settings = editor.config.settings(FileType::default());
+        editor.layout.view_mut().set_keep_backup(editor.keep_backup);
+        editor.layout.view_mut().apply_settings(settings);
+        editor.persist_history = !args.iter().skip(1).any(|arg| arg == "--no-history");
+        editor.buffers.push(editor.layout.view().clone());
+
+        // `+line` goes with the file after it, `file:line:col` carries its own
+        let mut pending_go_to = None;
+        for arg in args.iter().skip(1).filter(|arg| !arg.starts_with("--")) {
+            if let Some(line) = arg.strip_prefix('+').and_then(|line| line.parse().ok()) {
+                pending_go_to = Some(GoTo::Line { line, col: None });
+                continue;
+            }
+
+            let (file_name, go_to) = GoTo::split_file_name(arg);
+            let go_to = go_to.or(pending_go_to.take());
+            if editor.open_file(file_name) {
+                if let Some(go_to) = go_to {
+                    editor.go_to(go_to);
+                }
+            }
+        }
+
+        // start out with the first file, like it was the only one
+        editor.show_buffer(0);
+        editor.check_leftover_swaps();
+
+        editor.refrest_status();
+
+        Ok(editor)
+    }
+
+    // END SECTION
+
+    // SECTION: event loop
+
+    pub fn run(&mut self) {
+        loop {
+            self.refresh_screen();
+
+            if self.should_exit {
+                for view in &mut self.buffers {
+                    view.remove_swap();
+                }
+                break;
+            }
+
+            // The swap file is brought up to date once no key was pressed for a while.
+            // Swap files are best effort, failing to write one shouldn't get in the way of editing.
+            match poll(self.config.auto_save_interval) {
+                Ok(false) => {
+                    let _ = self.layout.view_mut().update_swap(true);
+                    continue;
+                }
+                Ok(true) => match read() {
+                    Ok(event) => self.evaluate_event(event),
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        {
+                            panic!("Could not read event: {err:?}");
+                        }
+                    }
+                },
+                Err(err) => {
+                    #[cfg(debug_assertions)]
+                    {
+                        panic!("Could not poll event: {err:?}");
+                    }
+                }
+            }
+
+            let _ = self.layout.view_mut().update_swap(false);
+            self.refrest_status();
+        }
+    }
+
+    fn refresh_screen(&mut self) {
+        if self.terminal_size.height == 0 || self.terminal_size.width == 0 {
+            return;
+        }
+        let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
+        let bottom_bar_origin = Position {
+            row: bottom_bar_row,
+            col: 0,
+        };
+        let _ = Terminal::hide_caret();
+        if self.in_prompt() {
+            self.command_bar.render(bottom_bar_origin);
+        } else {
+            self.message_bar.render(bottom_bar_origin);
+        }
+
+        if self.terminal_size.height > 1 {
+            self.status_bar.render(Position {
+                row: self.terminal_size.height.saturating_sub(2),
+                col: 0,
+            });
+        }
+
+        if self.terminal_size.height > 2 {
+            self.layout.render(Position::default());
+        }
+
+        let new_caret_pos = if self.in_prompt() {
+            Position {
+                row: bottom_bar_row,
+                col: self.command_bar.caret_position_col(),
+            }
+        } else {
+            self.layout.caret_position()
+        };
+
+        let _ = Terminal::move_caret_to(new_caret_pos);
+        let _ = Terminal::show_caret();
+        let _ = Terminal::execute();
+    }
+
+    fn refrest_status(&mut self) {
+        let status: DocumentStatus = self.layout.view().get_status();
+        let title: String = format!("{} - {NAME}", status.file_name);
+
+        self.status_bar.update_status(status);
+
+        if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
+            self.title = title;
+        }
+    }
+
+    fn evaluate_event(&mut self, event: Event) {
+        let should_process = match &event {
+            Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
+            Event::Resize(_, _) | Event::Paste(_) => true,
+            _ => false,
+        };
+
+        if !should_process {
+            return;
+        }
+
+        if let Event::Key(key_event) = event {
+            self.show_next_problem();
+            self.evaluate_key_event(key_event);
+        } else if let Ok(command) = Command::try_from(event) {
+            self.process_command(command);
+        }
+    }
+
+    // Problems with the config files are shown one at a time, the next one with every key pressed
+    fn show_next_problem(&mut self) {
+        if let Some(problem) = self.problems.pop_front() {
+            let more = match self.problems.len() {
+                0 => String::new(),
+                len => format!(" ({len} more, press a key for the next)"),
+            };
+            self.update_message(&format!("WARNING! {problem}{more}"));
+        }
+    }
+
+    // Keys of a sequence are shown while it's typed, so it's clear the editor is waiting for more
+    fn evaluate_key_event(&mut self, event: KeyEvent) {
+        match self.keymap.lookup(event) {
+            KeyLookup::Command(command) => {
+                if self.keymap_pending {
+                    self.keymap_pending = false;
+                    self.update_message("");
+                }
+                self.process_command(command);
+            }
+            KeyLookup::Pending(sequence) => {
+                self.keymap_pending = true;
+                self.update_message(&format!("{} …", keymap::describe(&sequence)));
+            }
+            KeyLookup::Unbound(sequence) => {
+                self.keymap_pending = false;
+                self.update_message(&format!("{} isn't bound.", keymap::describe(&sequence)));
+            }
+            KeyLookup::Ignored => {}
+        }
+    }
+
+    // ENDSECTION
+
+    // SECTION: commands handling
+
+    fn process_command(&mut self, command: Command) {
+        if let System(Resize(size)) = command {
+            self.handle_resize_command(size);
+            return;
+        }
+        match self.prompt_type {
+            PromptType::Search => self.process_command_during_search(command),
+            PromptType::Replace => self.process_command_during_replace(command),
+            PromptType::ReplaceWith => self.process_command_during_replace_with(command),
+            PromptType::ConfirmReplace => self.process_command_during_replace_confirmation(command),
+            PromptType::Save => self.process_command_during_save(command),
+            PromptType::Recover => self.process_command_during_recovery(command),
+            PromptType::ConfirmOverwrite => self.process_command_during_overwrite(command),
+            PromptType::ConfirmReload => self.process_command_during_reload(command),
+            PromptType::Open => self.process_command_during_open(command),
+            PromptType::ConfirmClose => self.process_command_during_close(command),
+            PromptType::GoToLine => self.process_command_during_go_to_line(command),
+            PromptType::None => self.process_command_no_prompt(command),
+        }
+    }
+
+    fn process_command_no_prompt(&mut self, command: Command) {
+        if matches!(command, System(Quit)) {
+            self.handle_quit_command();
+            return;
+        }
+
+        self.reset_quit_times();
+
+        let modifies_document = matches!(
+            command,
+            Edit(_)
+                | Clipboard(Cut | Paste)
+                | BracketedPaste(_)
+                | System(ToggleLineEnding | Replace)
+        );
+
+        if modifies_document && self.layout.view().is_read_only() {
+            self.update_message("File is read-only.");
+            return;
+        }
+
+        match command {
+            System(Quit | Resize(_) | ToggleRegex | CycleCaseSensitivity | ToggleWholeWord) => {}
+            System(Dismiss) => self.layout.view_mut().clear_selection(),
+            System(Save) => self.handle_save_command(),
+            System(Reload) => self.handle_reload_command(),
+            System(ToggleLineEnding) => {
+                let line_ending = self.layout.view_mut().toggle_line_ending();
+                self.update_message(&format!("Line endings set to {line_ending}."));
+            }
+            System(CycleLineNumbers) => {
+                let line_numbers = self.layout.view_mut().cycle_line_numbers();
+                self.update_message(&format!("Line numbers: {line_numbers}."));
+            }
+            System(ToggleSoftWrap) => {
+                if self.layout.view_mut().toggle_soft_wrap() {
+                    self.update_message("Soft wrap on.");
+                } else {
+                    self.update_message("Soft wrap off.");
+                }
+            }
+            System(Search) => self.set_prompt(PromptType::Search),
+            System(Replace) => self.set_prompt(PromptType::Replace),
+            System(GoToLine) => self.set_prompt(PromptType::GoToLine),
+            System(Open) => self.set_prompt(PromptType::Open),
+            System(NextBuffer) => self.cycle_buffers(true),
+            System(PreviousBuffer) => self.cycle_buffers(false),
+            System(CloseBuffer) => self.handle_close_command(),
+            System(ListBuffers) => self.list_buffers(),
+            Pane(pane_command) => self.process_pane_command(pane_command),
+            Edit(edit_command) => self.layout.view_mut().handle_edit_command(edit_command),
+            Move(move_command, selection_mode) => {
+                self.layout
+                    .view_mut()
+                    .handle_move_command(move_command, selection_mode);
+            }
+            Clipboard(Cut) => self.handle_cut_command(),
+            Clipboard(Copy) => self.handle_copy_command(),
+            Clipboard(Paste) => self.layout.view_mut().insert_text(&self.clipboard),
+            BracketedPaste(text) => self.layout.view_mut().insert_text(&text),
+        }
+
+        if modifies_document {
+            self.sync_views();
+        }
+    }
+
+    // SECTION: clipboard handling
+
+    fn handle_cut_command(&mut self) {
+        if let Some(text) = self.layout.view_mut().cut_selection() {
+            self.set_clipboard(text);
+        } else {
+            self.update_message("Nothing selected to cut.");
+        }
+    }
+
+    fn handle_copy_command(&mut self) {
+        if let Some(text) = self.layout.view().selected_text() {
+            self.set_clipboard(text);
+        } else {
+            self.update_message("Nothing selected to copy.");
+        }
+    }
+
+    fn set_clipboard(&mut self, text: String) {
+        // the host terminal might not support OSC 52, the internal clipboard works regardless
+        let _ = Terminal::copy_to_clipboard(&text);
+        self.clipboard = text;
+    }
+
+    // END SECTION
+
+    // SECTION: save command and prompt handling
+
+    fn handle_save_command(&mut self) {
+        if self.layout.view().has_changed_on_disk() {
+            self.set_prompt(PromptType::ConfirmOverwrite);
+        } else if self.layout.view().is_file_loaded() {
+            self.save_file(None);
+        } else {
+            self.set_prompt(PromptType::Save);
+        }
+    }
+
+    fn process_command_during_save(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Save aborted.");
+            }
+            System(_) => {}
+            Edit(InsertNewline) => {
+                let file_name = self.command_bar.value();
+                self.add_to_history();
+                self.save_file(Some(&file_name));
+                self.set_prompt(PromptType::None);
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            Move(Up, _) => {
+                self.browse_history(true);
+            }
+            Move(Down, _) => {
+                self.browse_history(false);
+            }
+            Move(move_command, _) => self.command_bar.handle_move_command(move_command),
+            Clipboard(Paste) => self.command_bar.insert_text(&self.clipboard),
+            Clipboard(Cut | Copy) | Pane(_) => {}
+            BracketedPaste(text) => self.command_bar.insert_text(&text),
+        }
+    }
+
+    fn process_command_during_overwrite(&mut self, command: Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                self.save_file(None);
+            }
+            Edit(Insert('n' | 'N')) | System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Save aborted.");
+            }
+            _ => {}
+        }
+    }
+
+    fn save_file(&mut self, file_name: Option<&str>) {
+        let result = if let Some(name) = file_name {
+            self.layout.view_mut().save_as(name)
+        } else {
+            self.layout.view_mut().save_file()
+        };
+
+        match result {
+            Ok(()) => self.message_bar.update_message("File saved successfully"),
+            Err(err) => self
+                .message_bar
+                .update_message(&format!("ERR: Could not save file, {err}.")),
+        }
+    }
+
+    // END SECTION
+
+    // SECTION: reload command and prompt handling
+
+    fn handle_reload_command(&mut self) {
+        if !self.layout.view().is_file_loaded() {
+            self.update_message("There is no file to reload.");
+        } else if self.layout.view().get_status().is_modified {
+            self.set_prompt(PromptType::ConfirmReload);
+        } else {
+            self.reload_file();
+        }
+    }
+
+    fn process_command_during_reload(&mut self, command: Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                self.reload_file();
+            }
+            Edit(Insert('n' | 'N')) | System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Reload aborted.");
+            }
+            _ => {}
+        }
+    }
+
+    fn reload_file(&mut self) {
+        match self.layout.view_mut().reload() {
+            Ok(()) => {
+                self.sync_views();
+                self.update_message("File reloaded.");
+            }
+            Err(err) => self.update_message(&format!("ERR: Could not reload file: {err}")),
+        }
+    }
+
+    // END SECTION
+
+    // SECTION: swap file recovery prompt handling
+
+    fn process_command_during_recovery(&mut self, command: Command) {
+        match command {
+            // the buffer hasn't been touched yet, quitting keeps the swap file for the next start
+            System(Quit) => self.should_exit = true,
+            Edit(Insert('r' | 'R')) => {
+                self.set_prompt(PromptType::None);
+                match self.layout.view_mut().recover_swap() {
+                    Ok(()) => {
+                        self.sync_views();
+                        self.update_message("Unsaved changes recovered, save to keep them.");
+                    }
+                    Err(err) => {
+                        self.update_message(&format!("ERR: Could not read swap file: {err}"));
+                    }
+                }
+                self.check_leftover_swaps();
+            }
+            Edit(Insert('d' | 'D')) => {
+                self.set_prompt(PromptType::None);
+                match self.layout.view_mut().discard_swap() {
+                    Ok(()) => self.update_message("Unsaved changes discarded."),
+                    Err(err) => {
+                        self.update_message(&format!("ERR: Could not remove swap file: {err}"));
+                    }
+                }
+                self.check_leftover_swaps();
+            }
+            Edit(Insert('v' | 'V')) => {
+                let diff = self
+                    .layout
+                    .view()
+                    .swap_diff()
+                    .unwrap_or_else(|err| format!("Could not read swap file: {err}."));
+                self.command_bar
+                    .set_prompt(&format!("{diff} Recover or discard? (r/d): "));
+            }
+            _ => {}
+        }
+    }
+
+    // END SECTION
+
+    // SECTION: search command and prompt handling
+
+    fn process_command_during_search(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.layout.view_mut().dismiss_search();
+            }
+            System(ToggleRegex) => {
+                self.change_search_options(|options| options.is_regex = !options.is_regex);
+            }
+            System(CycleCaseSensitivity) => self.change_search_options(|options| {
+                options.case_sensitivity = options.case_sensitivity.next();
+            }),
+            System(ToggleWholeWord) => {
+                self.change_search_options(|options| options.whole_word = !options.whole_word);
+            }
+            System(_) => {}
+            Edit(InsertNewline) => {
+                self.add_to_history();
+                self.set_prompt(PromptType::None);
+                self.layout.view_mut().exit_search();
+            }
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.update_search();
+            }
+            Move(Up, _) => {
+                let wrapped = self.layout.view_mut().prev_search_result();
+                self.update_search_hint(wrapped.then_some("wrapped to the bottom"));
+            }
+            Move(Down, _) => {
+                let wrapped = self.layout.view_mut().next_search_result();
+                self.update_search_hint(wrapped.then_some("wrapped to the top"));
+            }
+            Move(PageUp, _) => {
+                if self.browse_history(true) {
+                    self.update_search();
+                }
+            }
+            Move(PageDown, _) => {
+                if self.browse_history(false) {
+                    self.update_search();
+                }
+            }
+            Move(move_command, _) => self.command_bar.handle_move_command(move_command),
+            Clipboard(Paste) => {
+                self.command_bar.insert_text(&self.clipboard);
+                self.update_search();
+            }
+            BracketedPaste(text) => {
+                self.command_bar.insert_text(&text);
+                self.update_search();
+            }
+            Clipboard(Cut | Copy) | Pane(_) => {}
+        }
+    }
+
+    // Searches for the query in the prompt. A query that isn't a valid pattern clears the matches,
+    // and what's wrong with it is shown next to it.
+    fn update_search(&mut self) {
+        let query = self.command_bar.value();
+
+        match self.search_options.compile(&query) {
+            Ok(pattern) => {
+                self.layout.view_mut().search(&pattern);
+                self.update_search_hint(None);
+            }
+            Err(err) => {
+                self.command_bar
+                    .set_hint(&format!("  (invalid regex: {err})"));
+                self.layout.view_mut().clear_search_result();
+            }
+        }
+    }
+
+    // Tells which match the caret is at and how many there are, or that there is none
+    fn update_search_hint(&mut self, notice: Option<&str>) {
+        let hint = match self.layout.view().search_position() {
+            Some((current, count)) => {
+                let notice = notice
+                    .map(|notice| format!(", {notice}"))
+                    .unwrap_or_default();
+                format!("  (match {current} of {count}{notice})")
+            }
+            None if self.command_bar.value().is_empty() => String::new(),
+            None => "  (no matches)".to_string(),
+        };
+
+        self.command_bar.set_hint(&hint);
+    }
+
+    fn change_search_options(&mut self, change: impl FnOnce(&mut SearchOptions)) {
+        change(&mut self.search_options);
+        let title = if self.prompt_type == PromptType::Replace {
+            "Replace"
+        } else {
+            "Search"
+        };
+        self.command_bar.set_prompt(&self.search_prompt(title));
+        self.update_search();
+    }
+
+    fn search_prompt(&self, title: &str) -> String {
+        let options = self.search_options.to_string();
+        let mode = if options.is_empty() {
+            String::new()
+        } else {
+            format!(" [{options}]")
+        };
+
+        format!("{title}{mode} (Esc: cancel, ↑/↓: matches, PgUp/PgDn: history, Alt-R/C/B: regex/case/word): ")
+    }
+
+    // END SECTION
+
+    // SECTION: replace command and prompt handling
+
+    // The search term is entered just like in the search prompt, only Enter moves on to the replacement
+    fn process_command_during_replace(&mut self, command: Command) {
+        if matches!(command, Edit(InsertNewline)) {
+            self.ask_for_replacement();
+        } else {
+            self.process_command_during_search(command);
+        }
+    }
+
+    fn ask_for_replacement(&mut self) {
+        let query = self.command_bar.value();
+        self.add_to_history();
+        let count = self.layout.view().search_result_count();
+
+        let pattern = match self.search_options.compile(&query) {
+            Ok(pattern) if count > 0 => pattern,
+            _ => {
+                self.set_prompt(PromptType::None);
+                self.layout.view_mut().exit_search();
+                self.update_message(&format!("No matches for \"{query}\"."));
+                return;
+            }
+        };
+
+        self.replace_info = Some(ReplaceInfo::new(query, pattern, count));
+        self.set_prompt(PromptType::ReplaceWith);
+    }
+
+    fn process_command_during_replace_with(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => {
+                self.replace_info = None;
+                self.set_prompt(PromptType::None);
+                self.layout.view_mut().dismiss_search();
+                self.update_message("Replace aborted.");
+            }
+            System(_) | Pane(_) => {}
+            Edit(InsertNewline) => {
+                let replacement = self.command_bar.value();
+                self.add_to_history();
+                if let Some(replace_info) = &mut self.replace_info {
+                    replace_info.replacement = if self.search_options.is_regex {
+                        replacement
+                    } else {
+                        replacement.replace('$', "$$")
+                    };
+                }
+                self.layout.view_mut().begin_replace();
+                self.set_prompt(PromptType::ConfirmReplace);
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            Move(Up, _) => {
+                self.browse_history(true);
+            }
+            Move(Down, _) => {
+                self.browse_history(false);
+            }
+            Move(move_command, _) => self.command_bar.handle_move_command(move_command),
+            Clipboard(Paste) => self.command_bar.insert_text(&self.clipboard),
+            Clipboard(Cut | Copy) => {}
+            BracketedPaste(text) => self.command_bar.insert_text(&text),
+        }
+    }
+
+    fn process_command_during_replace_confirmation(&mut self, command: Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => self.replace_next_match(true),
+            Edit(Insert('n' | 'N')) => self.replace_next_match(false),
+            Edit(Insert('a' | 'A')) => {
+                while self.replace_info.is_some() {
+                    self.replace_next_match(true);
+                }
+            }
+            Edit(Insert('q' | 'Q')) | System(Dismiss) => self.finish_replace(),
+            _ => {}
+        }
+    }
+
+    // Replaces or skips the current match and moves on to the next one, finishing after the last one
+    fn replace_next_match(&mut self, should_replace: bool) {
+        let Some(replace_info) = &mut self.replace_info else {
+            return;
+        };
+
+        let view = self.layout.view_mut();
+        if !should_replace {
+            view.next_search_result();
+        } else if view.replace_current_match(&replace_info.pattern, &replace_info.replacement) {
+            replace_info.replaced = replace_info.replaced.saturating_add(1);
+        }
+        replace_info.remaining = replace_info.remaining.saturating_sub(1);
+        let is_done = replace_info.remaining == 0;
+
+        self.sync_views();
+        if is_done {
+            self.finish_replace();
+        }
+    }
+
+    fn finish_replace(&mut self) {
+        let replaced = self
+            .replace_info
+            .take()
+            .map_or(0, |replace_info| replace_info.replaced);
+
+        self.set_prompt(PromptType::None);
+        self.layout.view_mut().end_replace();
+        self.sync_views();
+
+        let noun = if replaced == 1 {
+            "occurrence"
+        } else {
+            "occurrences"
+        };
+        self.update_message(&format!("Replaced {replaced} {noun}."));
+    }
+
+    // END SECTION
+
+    // SECTION: quit command handling
+
+    #[allow(clippy::arithmetic_side_effects)]
+    fn handle_quit_command(&mut self) {
+        let modified_count = self
+            .buffers
+            .iter()
+            .filter(|view| view.get_status().is_modified)
+            .count();
+
+        if modified_count == 0 || self.quit_times + 1 == self.config.quit_times {
+            self.should_exit = true;
+        } else {
+            let files = if modified_count == 1 {
+                "File has".to_string()
+            } else {
+                format!("{modified_count} files have")
+            };
+            self.update_message(&format!(
+                "WARNING! {files} unsaved changes. Press Ctrl-Q {} more times to quit.",
+                self.config.quit_times - self.quit_times - 1
+            ));
+
+            self.quit_times += 1;
+        }
+    }
+
+    fn reset_quit_times(&mut self) {
+        if self.quit_times > 0 {
+            self.quit_times = 0;
+            self.message_bar.update_message("");
+        }
+    }
+
+    // END SECTION
+
+    // SECTION: buffer handling
+
+    fn view_size(&self) -> Size {
+        Size {
+            height: self.terminal_size.height.saturating_sub(2),
+            width: self.terminal_size.width,
+        }
+    }
+
+    fn new_view(&self) -> View {
+        let mut view = View::default();
+        view.set_keep_backup(self.keep_backup);
+        view.apply_settings(self.config.settings(FileType::default()));
+        view
+    }
+
+    // Lets the other panes and the buffer list follow the edits made in the active pane.
+    // Once all of them did, the buffer doesn't need to keep those edits anymore.
+    fn sync_views(&mut self) {
+        self.layout.sync_views();
+        let current = self.layout.view();
+        for view in &mut self.buffers {
+            if view.shares_buffer_with(current) {
+                view.sync_with_buffer();
+            }
+        }
+        self.layout.view_mut().forget_synced_changes();
+    }
+
+    // Position of the buffer shown in the active pane in the buffer list
+    fn active_buffer(&self) -> usize {
+        self.buffers
+            .iter()
+            .position(|view| view.shares_buffer_with(self.layout.view()))
+            .unwrap_or(0)
+    }
+
+    fn find_buffer(&self, file_name: &str) -> Option<usize> {
+        let path = fs::canonicalize(file_name).unwrap_or_else(|_| PathBuf::from(file_name));
+
+        self.buffers.iter().position(|view| {
+            view.get_file_path()
+                .is_some_and(|view_path| fs::canonicalize(&view_path).unwrap_or(view_path) == path)
+        })
+    }
+
+    // Opens the file in a new buffer and shows it in the active pane, or switches to it if it's open already
+    // Shows the file in the active pane and returns whether that worked
+    fn open_file(&mut self, file_name: &str) -> bool {
+        if let Some(idx) = self.find_buffer(file_name) {
+            self.show_buffer(idx);
+            return true;
+        }
+
+        let mut view = self.new_view();
+
+        if let Err(err) = view.load_file(file_name) {
+            self.update_message(&format!("ERR: Could not open file {file_name}: {err}"));
+            return false;
+        }
+        view.apply_settings(self.config.settings(view.get_status().file_type));
+
+        if view.is_read_only() {
+            self.update_message(&format!(
+                "WARNING! {file_name} doesn't look like a text file, it was opened read-only."
+            ));
+        } else if view.has_mixed_line_endings() {
+            self.update_message(&format!(
+                "WARNING! {file_name} mixes LF and CRLF line endings, saving converts them all to {}.",
+                view.get_status().line_ending
+            ));
+        }
+
+        // the empty buffer the editor starts with makes room for the first file
+        let current = self.layout.view();
+        let is_untouched = !current.is_file_loaded() && !current.get_status().is_modified;
+        let is_shown_elsewhere = self
+            .layout
+            .views()
+            .filter(|pane| pane.shares_buffer_with(current))
+            .count()
+            > 1;
+
+        if is_untouched && !is_shown_elsewhere {
+            let active = self.active_buffer();
+            self.buffers[active] = view.clone();
+            self.layout.show(view);
+        } else {
+            self.buffers.push(view);
+            self.show_buffer(self.buffers.len().saturating_sub(1));
+        }
+        true
+    }
+
+    // Shows the buffer in the active pane, with the caret where it was when that buffer was last left
+    fn show_buffer(&mut self, idx: usize) {
+        let current = self.active_buffer();
+        if idx == current || idx >= self.buffers.len() {
+            return;
+        }
+
+        // the swap file of a buffer in the background isn't updated anymore, so bring it up to date now
+        let _ = self.layout.view_mut().update_swap(true);
+        self.layout.view_mut().mark_synced();
+        self.buffers[current] = self.layout.view().clone();
+
+        let mut view = self.buffers[idx].clone();
+        view.sync_with_buffer();
+        self.layout.show(view);
+    }
+
+    fn cycle_buffers(&mut self, forward: bool) {
+        let count = self.buffers.len();
+        if count <= 1 {
+            self.update_message("There is no other buffer.");
+            return;
+        }
+
+        let active = self.active_buffer();
+        let idx = if forward {
+            active.saturating_add(1) % count
+        } else {
+            active.checked_sub(1).unwrap_or(count.saturating_sub(1))
+        };
+        self.show_buffer(idx);
+        self.list_buffers();
+        self.check_leftover_swap();
+    }
+
+    // Shows the open buffers in the message bar, the active one in brackets and modified ones marked with `*`
+    fn list_buffers(&mut self) {
+        let active = self.active_buffer();
+        let list = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(idx, view)| {
+                let status = view.get_status();
+                let modified = if status.is_modified { "*" } else { "" };
+                let entry = format!("{} {}{modified}", idx.saturating_add(1), status.file_name);
+                if idx == active {
+                    format!("[{entry}]")
+                } else {
+                    entry
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" | ");
+
+        self.update_message(&format!("Buffers: {list}"));
+    }
+
+    fn handle_close_command(&mut self) {
+        if self.layout.view().get_status().is_modified {
+            self.set_prompt(PromptType::ConfirmClose);
+        } else {
+            self.close_buffer();
+        }
+    }
+
+    // Closes the active buffer. Every pane showing it moves on to the next buffer.
+    fn close_buffer(&mut self) {
+        let idx = self.active_buffer();
+        let mut closed = self.buffers.remove(idx);
+        closed.remove_swap();
+
+        if self.buffers.is_empty() {
+            self.buffers.push(self.new_view());
+        }
+
+        let mut replacement = self.buffers[idx.min(self.buffers.len().saturating_sub(1))].clone();
+        replacement.sync_with_buffer();
+        self.layout
+            .replace_views_where(|view| view.shares_buffer_with(&closed), &replacement);
+
+        self.list_buffers();
+        self.check_leftover_swap();
+    }
+
+    fn process_command_during_close(&mut self, command: Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                self.close_buffer();
+            }
+            Edit(Insert('n' | 'N')) | System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Close aborted.");
+            }
+            _ => {}
+        }
+    }
+
+    fn process_command_during_open(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Open aborted.");
+            }
+            System(_) => {}
+            Edit(InsertNewline) => {
+                let file_name = self.command_bar.value();
+                self.add_to_history();
+                self.set_prompt(PromptType::None);
+                if self.open_file(&file_name) {
+                    self.check_leftover_swap();
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            Move(Up, _) => {
+                self.browse_history(true);
+            }
+            Move(Down, _) => {
+                self.browse_history(false);
+            }
+            Move(move_command, _) => self.command_bar.handle_move_command(move_command),
+            Clipboard(Paste) => self.command_bar.insert_text(&self.clipboard),
+            Clipboard(Cut | Copy) | Pane(_) => {}
+            BracketedPaste(text) => self.command_bar.insert_text(&text),
+        }
+    }
+
+    fn check_leftover_swap(&mut self) {
+        if self.layout.view().has_leftover_swap() {
+            self.set_prompt(PromptType::Recover);
+        }
+    }
+
+    // Asks about every buffer left with unsaved changes, one after another, starting with the one shown
+    fn check_leftover_swaps(&mut self) {
+        if !self.layout.view().has_leftover_swap() {
+            if let Some(idx) = self.buffers.iter().position(View::has_leftover_swap) {
+                self.show_buffer(idx);
+            }
+        }
+        self.check_leftover_swap();
+    }
+
+    // END SECTION
+
+    // SECTION: go to line
+
+    fn process_command_during_go_to_line(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            System(_) => {}
+            Edit(InsertNewline) => match self.command_bar.value().parse::<GoTo>() {
+                Ok(go_to) => {
+                    self.add_to_history();
+                    self.set_prompt(PromptType::None);
+                    self.go_to(go_to);
+                }
+                Err(err) => self.command_bar.set_hint(&format!("  ({err})")),
+            },
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.command_bar.set_hint("");
+            }
+            Move(Up, _) => {
+                self.browse_history(true);
+            }
+            Move(Down, _) => {
+                self.browse_history(false);
+            }
+            Move(move_command, _) => self.command_bar.handle_move_command(move_command),
+            Clipboard(Paste) => self.command_bar.insert_text(&self.clipboard),
+            Clipboard(Cut | Copy) | Pane(_) => {}
+            BracketedPaste(text) => self.command_bar.insert_text(&text),
+        }
+    }
+
+    fn go_to(&mut self, go_to: GoTo) {
+        let status = self.layout.view().get_status();
+        let location = go_to.resolve(status.current_line_index, status.total_lines);
+        self.layout.view_mut().go_to(location);
+    }
+
+    // END SECTION
+
+    // SECTION: pane handling
+
+    fn process_pane_command(&mut self, command: PaneCommand) {
+        match command {
+            PaneCommand::SplitHorizontal => self.layout.split(SplitDirection::Horizontal),
+            PaneCommand::SplitVertical => self.layout.split(SplitDirection::Vertical),
+            PaneCommand::Close => {
+                if !self.layout.close_active() {
+                    self.update_message("This is the only pane.");
+                }
+            }
+            PaneCommand::FocusNext => self.layout.focus_next(),
+        }
+    }
+
+    // END SECTION
+
+    // SECTION: message and command bar
+
+    fn update_message(&mut self, new_message: &str) {
+        self.message_bar.update_message(new_message);
+    }
+
+    // END SECTION
+
+    // SECTION: resize command handling
+    fn handle_resize_command(&mut self, size: Size) {
+        self.terminal_size = size;
+
+        self.layout.resize(self.view_size());
+
+        let bar_size = Size {
+            height: 1,
+            width: size.width,
+        };
+
+        self.message_bar.resize(bar_size);
+        self.status_bar.resize(bar_size);
+        self.command_bar.resize(bar_size);
+    }
+
+    // END SECTION
+
+    // SECTION: prompt handling
+    fn in_prompt(&self) -> bool {
+        !self.prompt_type.is_none()
+    }
+
+    fn set_prompt(&mut self, prompt_type: PromptType) {
+        match prompt_type {
+            PromptType::None => self.message_bar.set_needs_redraw(true),
+            PromptType::Save => self.command_bar.set_prompt("Save as: "),
+            PromptType::Open => self.command_bar.set_prompt("Open file: "),
+            PromptType::GoToLine => self
+                .command_bar
+                .set_prompt("Go to line (line, line:col, +N/-N or N%): "),
+            PromptType::ConfirmClose => {
+                let file_name = self.layout.view().get_status().file_name;
+                self.command_bar.set_prompt(&format!(
+                    "Discard unsaved changes in {file_name} and close it? (y/n): "
+                ));
+            }
+            PromptType::ConfirmOverwrite => self
+                .command_bar
+                .set_prompt("The file changed on disk since it was opened. Overwrite it? (y/n): "),
+            PromptType::ConfirmReload => self
+                .command_bar
+                .set_prompt("Discard unsaved changes and reload the file? (y/n): "),
+            PromptType::Recover => self.command_bar.set_prompt(
+                "Found unsaved changes from an earlier session. Recover, discard or view diff? (r/d/v): ",
+            ),
+            PromptType::Search => {
+                self.command_bar.set_prompt(&self.search_prompt("Search"));
+                self.layout.view_mut().enter_search();
+            }
+            PromptType::Replace => {
+                self.command_bar.set_prompt(&self.search_prompt("Replace"));
+                self.layout.view_mut().enter_search();
+            }
+            PromptType::ReplaceWith => {
+                let query = self
+                    .replace_info
+                    .as_ref()
+                    .map(|replace_info| replace_info.query.clone())
+                    .unwrap_or_default();
+                self.command_bar
+                    .set_prompt(&format!("Replace \"{query}\" with (Esc to cancel): "));
+            }
+            PromptType::ConfirmReplace => self
+                .command_bar
+                .set_prompt("Replace this match? (y = yes, n = no, a = all, q = quit): "),
+        }
+
+        self.command_bar.clear_value();
+        self.prompt_type = prompt_type;
+
+        if let Some(history) = self.prompt_history() {
+            history.reset();
+        }
+    }
+
+    fn prompt_history(&mut self) -> Option<&mut PromptHistory> {
+        let name = self.prompt_type.history_name()?;
+        let persist = self.persist_history;
+
+        Some(
+            self.histories
+                .entry(name)
+                .or_insert_with(|| PromptHistory::load(name, persist)),
+        )
+    }
+
+    // Remembers the value entered into the current prompt
+    fn add_to_history(&mut self) {
+        let value = self.command_bar.value();
+        if let Some(history) = self.prompt_history() {
+            history.add(&value);
+        }
+    }
+
+    // Shows an older or newer entry of the prompt's history in place of the value.
+    // Returns false if there is none, leaving the value as it is.
+    fn browse_history(&mut self, older: bool) -> bool {
+        let current = self.command_bar.value();
+        let Some(history) = self.prompt_history() else {
+            return false;
+        };
+
+        let entry = if older {
+            history.previous(&current)
+        } else {
+            history.next()
+        }
+        .map(str::to_string);
+
+        match entry {
+            Some(entry) => {
+                self.command_bar.set_value(&entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // END SECTION
+}
+
+impl Drop for Editor {
+    fn drop(&mut self) {
+        let _ = Terminal::terminate();
+
+        if self.should_exit {
+            let _ = Terminal::print("Farewell!!!\r\n");
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    env, fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+// Older entries are dropped once a history grows past this
+const MAX_ENTRIES: usize = 100;
+
+// What was entered into one kind of prompt, oldest first
+#[derive(Default)]
+pub struct PromptHistory {
+    entries: Vec<String>,
+    position: Option<usize>, // the entry shown while browsing, None when at the value being typed
+    draft: String,           // the value being typed, kept while browsing
+    file: Option<PathBuf>,   // where the history is kept between sessions
+}
+
+impl PromptHistory {
+    // Loads the history of the prompt from the data directory if `persist` is set, otherwise it starts out empty
+    pub fn load(name: &str, persist: bool) -> Self {
+        let file = persist
+            .then(data_dir)
+            .flatten()
+            .map(|dir| dir.join(format!("{name}_history")));
+
+        let entries = file
+            .as_deref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Self {
+            entries,
+            file,
+            ..Self::default()
+        }
+    }
+
+    // Remembers the entry as the most recent one. An earlier copy of it is moved to the end.
+    pub fn add(&mut self, entry: &str) {
+        self.reset();
+        if entry.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|existing| existing != entry);
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries
+                .drain(..self.entries.len().saturating_sub(MAX_ENTRIES));
+        }
+
+        if let Some(file) = &self.file {
+            // losing the history is no reason to bother the user
+            let _ = write_entries(file, &self.entries);
+        }
+    }
+
+    // Goes back to the value being typed
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    // Returns the entry before the one shown, `current` being the value typed so far.
+    // None if there's nothing older.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(position) => position.checked_sub(1)?,
+        };
+
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+
+    // Returns the entry after the one shown, ending with the value that was typed before browsing.
+    // None if not browsing.
+    pub fn next(&mut self) -> Option<&str> {
+        let next = self.position?.saturating_add(1);
+
+        if next < self.entries.len() {
+            self.position = Some(next);
+            self.entries.get(next).map(String::as_str)
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+}
+
+// `$XDG_DATA_HOME/hecto`, or `~/.local/share/hecto` if it isn't set
+fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+
+    Some(base.join("hecto"))
+}
+
+fn write_entries(file: &Path, entries: &[String]) -> Result<(), Error> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut contents = entries.join("\n");
+    contents.push('\n');
+    fs::write(file, contents)
+}
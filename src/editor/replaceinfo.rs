@@ -0,0 +1,22 @@
+use regex::Regex;
+
+// A replace in progress, stepping through the matches and asking about each one
+pub struct ReplaceInfo {
+    pub query: String,
+    pub pattern: Regex,
+    pub replacement: String, // `$` refers to capture groups, it's escaped as `$$` when not in regex mode
+    pub remaining: usize,    // matches that weren't asked about yet
+    pub replaced: usize,
+}
+
+impl ReplaceInfo {
+    pub const fn new(query: String, pattern: Regex, remaining: usize) -> Self {
+        Self {
+            query,
+            pattern,
+            replacement: String::new(),
+            remaining,
+            replaced: 0,
+        }
+    }
+}
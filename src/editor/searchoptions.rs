@@ -0,0 +1,257 @@
+use regex::{Error as RegexError, Regex, RegexBuilder};
+use std::fmt::{Display, Formatter, Result};
+
+// Matches anywhere but between two word characters. Unlike `\b`, this also works
+// for queries that start or end with punctuation, like `foo(`.
+const NOT_INSIDE_WORD: &str = r"(?:\b{start-half}|\b{end-half})";
+
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub enum CaseSensitivity {
+    #[default]
+    Sensitive,
+    Insensitive,
+    Smart, // insensitive, unless the query has an uppercase letter in it
+}
+
+impl CaseSensitivity {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+            Self::Smart => Self::Sensitive,
+        }
+    }
+}
+
+// How the query typed into the search prompt is matched against the document
+#[derive(Clone, Copy, Default)]
+pub struct SearchOptions {
+    pub is_regex: bool,
+    pub case_sensitivity: CaseSensitivity,
+    pub whole_word: bool, // matches may neither start nor end in the middle of a word
+}
+
+impl SearchOptions {
+    // Builds the pattern to search for. Outside of regex mode the query is matched literally.
+    // Errors are turned into a short message that fits into the command bar.
+    pub fn compile(self, query: &str) -> std::result::Result<Regex, String> {
+        let mut pattern = if self.is_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+
+        if self.whole_word {
+            // wrapping a pattern with a stray `)` in it could make it valid, so check it on its own first
+            if self.is_regex {
+                Regex::new(&pattern).map_err(|err| describe_error(&err))?;
+            }
+            pattern = format!("{NOT_INSIDE_WORD}(?:{pattern}){NOT_INSIDE_WORD}");
+        }
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignores_case(query))
+            .build()
+            .map_err(|err| describe_error(&err))
+    }
+
+    fn ignores_case(self, query: &str) -> bool {
+        match self.case_sensitivity {
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+            CaseSensitivity::Smart => !self.has_uppercase(query),
+        }
+    }
+
+    // In a regex, escapes like `\W` or `\S` don't count as uppercase letters
+    fn has_uppercase(self, query: &str) -> bool {
+        let mut is_escaped = false;
+
+        for character in query.chars() {
+            if is_escaped {
+                is_escaped = false;
+            } else if self.is_regex && character == '\\' {
+                is_escaped = true;
+            } else if character.is_uppercase() {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+// Lists the options that are switched on, e.g. "regex, ignore case"
+impl Display for SearchOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let case_sensitivity = match self.case_sensitivity {
+            CaseSensitivity::Sensitive => None,
+            CaseSensitivity::Insensitive => Some("ignore case"),
+            CaseSensitivity::Smart => Some("smart case"),
+        };
+
+        let options: Vec<&str> = [
+            self.is_regex.then_some("regex"),
+            case_sensitivity,
+            self.whole_word.then_some("whole word"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        write!(f, "{}", options.join(", "))
+    }
+}
+
+// Syntax errors come as several lines that point at the offending spot, only the last one says what's wrong
+fn describe_error(err: &RegexError) -> String {
+    match err {
+        RegexError::Syntax(message) => message
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix("error: "))
+            .unwrap_or("invalid syntax")
+            .to_string(),
+        RegexError::CompiledTooBig(_) => "pattern is too big".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal() -> SearchOptions {
+        SearchOptions::default()
+    }
+
+    fn regex() -> SearchOptions {
+        SearchOptions {
+            is_regex: true,
+            ..SearchOptions::default()
+        }
+    }
+
+    fn with_case(case_sensitivity: CaseSensitivity) -> SearchOptions {
+        SearchOptions {
+            case_sensitivity,
+            ..SearchOptions::default()
+        }
+    }
+
+    fn whole_word(is_regex: bool) -> SearchOptions {
+        SearchOptions {
+            is_regex,
+            whole_word: true,
+            ..SearchOptions::default()
+        }
+    }
+
+    fn matches(options: SearchOptions, query: &str, text: &str) -> Vec<String> {
+        options
+            .compile(query)
+            .unwrap()
+            .find_iter(text)
+            .map(|found| found.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn literal_queries_match_special_characters_as_they_are() {
+        assert_eq!(matches(literal(), "a.b", "axb a.b"), ["a.b"]);
+        assert_eq!(matches(literal(), "v[0]*", "v[0]* v0"), ["v[0]*"]);
+        assert_eq!(matches(literal(), r"\d+", r"12 \d+"), [r"\d+"]);
+        assert!(literal().compile("(unbalanced").is_ok());
+    }
+
+    #[test]
+    fn regex_queries_are_patterns() {
+        assert_eq!(matches(regex(), "a.b", "axb a.b"), ["axb", "a.b"]);
+        assert_eq!(matches(regex(), r"\d+", "x12 y345"), ["12", "345"]);
+        assert_eq!(
+            matches(regex(), "fo+|ba[rz]", "fooo bar baz"),
+            ["fooo", "bar", "baz"]
+        );
+    }
+
+    #[test]
+    fn invalid_regexes_get_a_short_message() {
+        let message = regex().compile("(unbalanced").unwrap_err();
+        assert_eq!(message, "unclosed group");
+        assert!(!message.contains('\n'));
+    }
+
+    #[test]
+    fn case_sensitivity_applies_as_chosen() {
+        let text = "Foo foo FOO";
+        assert_eq!(
+            matches(with_case(CaseSensitivity::Sensitive), "foo", text),
+            ["foo"]
+        );
+        assert_eq!(
+            matches(with_case(CaseSensitivity::Insensitive), "Foo", text),
+            ["Foo", "foo", "FOO"]
+        );
+    }
+
+    #[test]
+    fn smart_case_only_ignores_case_for_lowercase_queries() {
+        let smart = with_case(CaseSensitivity::Smart);
+        let text = "Foo foo FOO";
+        assert_eq!(matches(smart, "foo", text), ["Foo", "foo", "FOO"]);
+        assert_eq!(matches(smart, "Foo", text), ["Foo"]);
+        assert_eq!(matches(smart, "ü", "Ü ü"), ["Ü", "ü"]);
+        assert_eq!(matches(smart, "Ü", "Ü ü"), ["Ü"]);
+    }
+
+    #[test]
+    fn smart_case_ignores_regex_escapes() {
+        let smart = SearchOptions {
+            is_regex: true,
+            case_sensitivity: CaseSensitivity::Smart,
+            whole_word: false,
+        };
+        assert_eq!(matches(smart, r"a\Sc", "ABC abc"), ["ABC", "abc"]);
+        assert_eq!(matches(smart, r"\wB", "ab AB"), ["AB"]);
+        assert_eq!(
+            matches(
+                SearchOptions {
+                    is_regex: false,
+                    ..smart
+                },
+                r"a\S",
+                r"A\S a\S"
+            ),
+            [r"a\S"]
+        );
+    }
+
+    #[test]
+    fn whole_words_match_only_between_word_boundaries() {
+        let text = "cat concat cats cat_ cat.";
+        assert_eq!(matches(whole_word(false), "cat", text), ["cat", "cat"]);
+        assert_eq!(matches(whole_word(true), "ca.", text), ["cat", "cat"]);
+        assert_eq!(
+            matches(whole_word(true), "cat|concat", text),
+            ["cat", "concat", "cat"]
+        );
+    }
+
+    #[test]
+    fn whole_words_may_start_or_end_with_punctuation() {
+        assert_eq!(
+            matches(whole_word(false), "foo(", "foo( barfoo( foo(x"),
+            ["foo(", "foo("]
+        );
+        assert_eq!(
+            matches(whole_word(false), "->", "a->b a -> b"),
+            ["->", "->"]
+        );
+    }
+
+    #[test]
+    fn whole_word_mode_keeps_invalid_regexes_invalid() {
+        assert!(whole_word(true).compile("a)|(b").is_err());
+        assert!(whole_word(false).compile("a)|(b").is_ok());
+    }
+}
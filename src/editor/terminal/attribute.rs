@@ -1,95 +1,181 @@
-use crossterm::style::Color;
-
-use super::super::AnnotationType;
-
-pub struct Attribute {
-    pub foreground: Option<Color>,
-    pub background: Option<Color>,
-}
-
-impl From<AnnotationType> for Attribute {
-    fn from(annotation_type: AnnotationType) -> Self {
-        match annotation_type {
-            AnnotationType::Match => Self {
-                foreground: Some(Color::Rgb { r: 0, g: 0, b: 0 }),
-                background: Some(Color::Rgb {
-                    r: 211,
-                    g: 211,
-                    b: 211,
-                }),
-            },
-            AnnotationType::SelectedMatch => Self {
-                foreground: Some(Color::Rgb { r: 0, g: 0, b: 0 }),
-                background: Some(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 0,
-                }),
-            },
-            AnnotationType::Number => Self {
-                foreground: Some(Color::Rgb {
-                    r: 123,
-                    g: 160,
-                    b: 255,
-                }),
-                background: None,
-            },
-            AnnotationType::Keyword => Self {
-                foreground: Some(Color::Rgb {
-                    r: 217,
-                    g: 95,
-                    b: 237,
-                }),
-                background: None,
-            },
-            AnnotationType::Type => Self {
-                foreground: Some(Color::Rgb {
-                    r: 175,
-                    g: 225,
-                    b: 175,
-                }),
-                background: None,
-            },
-            AnnotationType::KnownValue => Self {
-                foreground: Some(Color::Rgb {
-                    r: 195,
-                    g: 100,
-                    b: 73,
-                }),
-                background: None,
-            },
-            AnnotationType::Char => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 191,
-                    b: 0,
-                }),
-                background: None,
-            },
-            AnnotationType::LifeTimeSpecifier => Self {
-                foreground: Some(Color::Rgb {
-                    r: 102,
-                    g: 205,
-                    b: 170,
-                }),
-                background: None,
-            },
-            AnnotationType::Comment => Self {
-                foreground: Some(Color::Rgb {
-                    r: 150,
-                    g: 150,
-                    b: 150,
-                }),
-                background: None,
-            },
-            AnnotationType::String => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 179,
-                    b: 102,
-                }),
-                background: None,
-            },
-        }
-    }
-}
+use crossterm::style::Color;
+
+use super::super::{AnnotationType, Theme};
+
+pub struct Attribute {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+}
+
+impl Attribute {
+    pub const fn new(annotation_type: AnnotationType, theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => Self::dark(annotation_type),
+            Theme::Light => Self::light(annotation_type),
+        }
+    }
+
+    const fn dark(annotation_type: AnnotationType) -> Self {
+        match annotation_type {
+            AnnotationType::Match => Self {
+                foreground: Some(Color::Rgb { r: 0, g: 0, b: 0 }),
+                background: Some(Color::Rgb {
+                    r: 211,
+                    g: 211,
+                    b: 211,
+                }),
+            },
+            AnnotationType::SelectedMatch => Self {
+                foreground: Some(Color::Rgb { r: 0, g: 0, b: 0 }),
+                background: Some(Color::Rgb {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                }),
+            },
+            AnnotationType::Selection => Self {
+                foreground: None,
+                background: Some(Color::Rgb {
+                    r: 68,
+                    g: 85,
+                    b: 119,
+                }),
+            },
+            AnnotationType::Number => Self {
+                foreground: Some(Color::Rgb {
+                    r: 123,
+                    g: 160,
+                    b: 255,
+                }),
+                background: None,
+            },
+            AnnotationType::Keyword => Self {
+                foreground: Some(Color::Rgb {
+                    r: 217,
+                    g: 95,
+                    b: 237,
+                }),
+                background: None,
+            },
+            AnnotationType::Type => Self {
+                foreground: Some(Color::Rgb {
+                    r: 175,
+                    g: 225,
+                    b: 175,
+                }),
+                background: None,
+            },
+            AnnotationType::KnownValue => Self {
+                foreground: Some(Color::Rgb {
+                    r: 195,
+                    g: 100,
+                    b: 73,
+                }),
+                background: None,
+            },
+            AnnotationType::Char => Self {
+                foreground: Some(Color::Rgb {
+                    r: 255,
+                    g: 191,
+                    b: 0,
+                }),
+                background: None,
+            },
+            AnnotationType::LifeTimeSpecifier => Self {
+                foreground: Some(Color::Rgb {
+                    r: 102,
+                    g: 205,
+                    b: 170,
+                }),
+                background: None,
+            },
+            AnnotationType::Comment => Self {
+                foreground: Some(Color::Rgb {
+                    r: 150,
+                    g: 150,
+                    b: 150,
+                }),
+                background: None,
+            },
+            AnnotationType::String => Self {
+                foreground: Some(Color::Rgb {
+                    r: 255,
+                    g: 179,
+                    b: 102,
+                }),
+                background: None,
+            },
+            AnnotationType::MatchingBracket => Self {
+                foreground: None,
+                background: Some(Color::Rgb {
+                    r: 95,
+                    g: 95,
+                    b: 135,
+                }),
+            },
+            AnnotationType::LineNumber => Self {
+                foreground: Some(Color::Rgb {
+                    r: 110,
+                    g: 110,
+                    b: 110,
+                }),
+                background: None,
+            },
+            AnnotationType::CurrentLineNumber => Self {
+                foreground: Some(Color::Rgb {
+                    r: 220,
+                    g: 220,
+                    b: 220,
+                }),
+                background: None,
+            },
+            AnnotationType::Tab => Self {
+                foreground: Some(Color::Rgb {
+                    r: 85,
+                    g: 85,
+                    b: 85,
+                }),
+                background: None,
+            },
+        }
+    }
+
+    // Darker shades of the same colours, so they stay readable on a white background
+    const fn light(annotation_type: AnnotationType) -> Self {
+        match annotation_type {
+            AnnotationType::Match => Self::background(rgb(200, 200, 200)),
+            AnnotationType::SelectedMatch => Self::background(rgb(255, 220, 0)),
+            AnnotationType::Selection => Self::background(rgb(180, 205, 240)),
+            AnnotationType::Number => Self::foreground(rgb(30, 80, 200)),
+            AnnotationType::Keyword => Self::foreground(rgb(150, 30, 170)),
+            AnnotationType::Type => Self::foreground(rgb(30, 120, 60)),
+            AnnotationType::KnownValue => Self::foreground(rgb(170, 60, 30)),
+            AnnotationType::Char => Self::foreground(rgb(160, 110, 0)),
+            AnnotationType::LifeTimeSpecifier => Self::foreground(rgb(20, 130, 110)),
+            AnnotationType::Comment => Self::foreground(rgb(120, 120, 120)),
+            AnnotationType::String => Self::foreground(rgb(190, 90, 0)),
+            AnnotationType::MatchingBracket => Self::background(rgb(190, 190, 230)),
+            AnnotationType::LineNumber => Self::foreground(rgb(160, 160, 160)),
+            AnnotationType::CurrentLineNumber => Self::foreground(rgb(40, 40, 40)),
+            AnnotationType::Tab => Self::foreground(rgb(200, 200, 200)),
+        }
+    }
+
+    const fn foreground(color: Color) -> Self {
+        Self {
+            foreground: Some(color),
+            background: None,
+        }
+    }
+
+    const fn background(color: Color) -> Self {
+        Self {
+            foreground: None,
+            background: Some(color),
+        }
+    }
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
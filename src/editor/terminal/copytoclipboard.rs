@@ -0,0 +1,44 @@
+use crossterm::Command;
+use std::fmt;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Asks the host terminal to place the text into the system clipboard, using the OSC 52 escape sequence.
+/// Since the sequence travels along with the rest of the output, this also works over SSH.
+/// Terminals which don't support OSC 52 silently ignore it.
+pub struct CopyToClipboard<'a>(pub &'a str);
+
+impl Command for CopyToClipboard<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "\x1b]52;c;{}\x07", encode_base64(self.0.as_bytes()))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[allow(clippy::as_conversions)]
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3).saturating_mul(4));
+
+    for chunk in bytes.chunks(3) {
+        let first = chunk.first().copied().unwrap_or_default();
+        let second = chunk.get(1).copied().unwrap_or_default();
+        let third = chunk.get(2).copied().unwrap_or_default();
+        let triple = u32::from(first) << 16 | u32::from(second) << 8 | u32::from(third);
+
+        for (idx, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if idx <= chunk.len() {
+                let sextet = (triple >> shift) & 0x3f;
+                result.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,21 @@
+use std::str::FromStr;
+
+// The colours the annotations are drawn in, see `Attribute`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "dark" => Ok(Self::Dark),
+            "light" => Ok(Self::Light),
+            _ => Err(format!("expected \"dark\" or \"light\", not \"{value}\"")),
+        }
+    }
+}
@@ -1,147 +1,231 @@
-use super::super::{
-    command::{Edit, Move},
-    Line, Position, Terminal,
-};
-use super::UIComponent;
-use crate::prelude::*;
-use std::{
-    cmp::{max, min},
-    io::Error,
-};
-
-#[derive(Default)]
-pub struct CommandBar {
-    prompt: Line,
-    value: Line,
-    needs_redraw: bool,
-    size: Size,
-    caret_position: Position,
-}
-
-impl CommandBar {
-    pub fn handle_edit_command(&mut self, command: Edit) {
-        match command {
-            Edit::Insert(character) => self.insert_char(character, self.caret_position_col()),
-            Edit::Delete => self.delete_char(self.caret_position_col()),
-            Edit::DeleteBackward => self.delete_char_backward(self.caret_position_col()),
-            Edit::InsertNewline => {}
-        }
-
-        self.set_needs_redraw(true);
-    }
-
-    pub fn handle_move_command(&mut self, command: Move) {
-        match command {
-            Move::Left => self.move_left(),
-            Move::Right => self.move_right(),
-            Move::StartOfLine | Move::Up | Move::PageUp => self.move_to_start_of_line(),
-            Move::EndOfLine | Move::Down | Move::PageDown => self.move_to_end_of_line(),
-        }
-    }
-
-    pub fn caret_position_col(&self) -> ColIdx {
-        min(self.caret_position.col, self.size.width)
-    }
-
-    pub fn value(&self) -> String {
-        self.value.to_string()
-    }
-
-    pub fn set_prompt(&mut self, prompt: &str) {
-        self.prompt = Line::from(prompt);
-        self.set_caret_postion(self.prompt.grapheme_count());
-        self.set_needs_redraw(true);
-    }
-
-    pub fn clear_value(&mut self) {
-        self.value = Line::default();
-        self.set_needs_redraw(true);
-    }
-
-    pub fn set_caret_postion(&mut self, col: usize) {
-        self.caret_position.col = col;
-    }
-
-    fn move_left(&mut self) {
-        self.caret_position.col = max(
-            self.caret_position.col.saturating_sub(1),
-            self.prompt.grapheme_count(),
-        );
-    }
-
-    fn move_right(&mut self) {
-        self.caret_position.col = min(
-            self.caret_position.col.saturating_add(1),
-            self.value.grapheme_count() + self.prompt.grapheme_count(),
-        );
-    }
-
-    fn move_to_start_of_line(&mut self) {
-        self.caret_position.col = self.prompt.grapheme_count();
-    }
-
-    fn move_to_end_of_line(&mut self) {
-        let max_width = self
-            .prompt
-            .grapheme_count()
-            .saturating_add(self.value.grapheme_count());
-
-        self.caret_position.col = min(max_width, self.size.width);
-    }
-
-    fn insert_char(&mut self, character: char, col: usize) {
-        self.value
-            .insert_char(character, col - self.prompt.grapheme_count());
-        self.caret_position.col = col + 1;
-    }
-
-    fn delete_char(&mut self, col: usize) {
-        self.value.delete_char(col - self.prompt.grapheme_count());
-    }
-
-    fn delete_char_backward(&mut self, col: usize) {
-        if self.caret_position.col == self.prompt.grapheme_count() {
-            return;
-        }
-
-        self.value
-            .delete_char(col - self.prompt.grapheme_count() - 1);
-        self.caret_position.col = col - 1;
-    }
-}
-
-impl UIComponent for CommandBar {
-    fn set_needs_redraw(&mut self, value: bool) {
-        self.needs_redraw = value;
-    }
-
-    fn needs_redraw(&self) -> bool {
-        self.needs_redraw
-    }
-
-    fn set_size(&mut self, size: Size) {
-        self.size = size;
-    }
-
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
-        let area_for_value = self.size.width.saturating_sub(self.prompt.grapheme_count());
-
-        let value_end = self.value.width();
-
-        let value_start = value_end.saturating_sub(area_for_value);
-
-        let message = format!(
-            "{}{}",
-            self.prompt,
-            self.value.get_visible_graphemes(value_start..value_end)
-        );
-
-        let to_print = if message.len() <= self.size.width {
-            message
-        } else {
-            String::new()
-        };
-
-        Terminal::print_row(origin, &to_print)
-    }
-}
+use super::super::{
+    command::{Edit, Move},
+    Line, Position, Terminal,
+};
+use super::UIComponent;
+use crate::prelude::*;
+use std::{
+    cmp::{max, min},
+    io::Error,
+};
+
+#[derive(Default)]
+pub struct CommandBar {
+    prompt: Line,
+    value: Line,
+    hint: Line, // shown after the value, e.g. to say what's wrong with it
+    needs_redraw: bool,
+    size: Size,
+    caret_position: Position,
+}
+
+impl CommandBar {
+    pub fn handle_edit_command(&mut self, command: Edit) {
+        match command {
+            Edit::Insert(character) => self.insert_char(character, self.caret_position.col),
+            Edit::Delete => self.delete_char(self.caret_position.col),
+            Edit::DeleteBackward => self.delete_char_backward(self.caret_position.col),
+            Edit::DeleteWordBackward => {
+                let end = self.caret_position.col;
+                self.move_word_left();
+                self.delete_until(end);
+            }
+            Edit::DeleteWord => {
+                let caret = self.caret_position.col;
+                self.move_word_right();
+                let end = self.caret_position.col;
+                self.set_caret_postion(caret);
+                self.delete_until(end);
+            }
+            Edit::InsertNewline | Edit::Undo | Edit::Redo => {}
+        }
+
+        self.set_needs_redraw(true);
+    }
+
+    // Inserts the text at the caret. The prompt holds a single line, so line breaks are dropped
+    pub fn insert_text(&mut self, text: &str) {
+        for character in text
+            .chars()
+            .filter(|character| !matches!(character, '\n' | '\r'))
+        {
+            self.insert_char(character, self.caret_position.col);
+        }
+
+        self.set_needs_redraw(true);
+    }
+
+    pub fn handle_move_command(&mut self, command: Move) {
+        match command {
+            Move::Left => self.move_left(),
+            Move::Right => self.move_right(),
+            Move::WordLeft => self.move_word_left(),
+            Move::WordRight => self.move_word_right(),
+            Move::StartOfLine
+            | Move::Up
+            | Move::PageUp
+            | Move::StartOfFile
+            | Move::PreviousParagraph => self.move_to_start_of_line(),
+            Move::EndOfLine
+            | Move::Down
+            | Move::PageDown
+            | Move::EndOfFile
+            | Move::NextParagraph => self.move_to_end_of_line(),
+            Move::MatchingBracket => {}
+        }
+    }
+
+    // Where the caret is on screen. Editing uses the unclamped position, which may be past a narrow terminal.
+    pub fn caret_position_col(&self) -> ColIdx {
+        min(self.caret_position.col, self.size.width)
+    }
+
+    pub fn value(&self) -> String {
+        self.value.to_string()
+    }
+
+    // Replaces the prompt. The caret stays where it was within the value.
+    pub fn set_prompt(&mut self, prompt: &str) {
+        let caret_in_value = self
+            .caret_position
+            .col
+            .saturating_sub(self.prompt.grapheme_count());
+        self.prompt = Line::from(prompt);
+        self.set_caret_postion(self.prompt.grapheme_count().saturating_add(caret_in_value));
+        self.set_needs_redraw(true);
+    }
+
+    // Replaces the value, with the caret at its end
+    pub fn set_value(&mut self, value: &str) {
+        self.value = Line::from(value);
+        self.set_caret_postion(
+            self.prompt
+                .grapheme_count()
+                .saturating_add(self.value.grapheme_count()),
+        );
+        self.set_needs_redraw(true);
+    }
+
+    pub fn set_hint(&mut self, hint: &str) {
+        self.hint = Line::from(hint);
+        self.set_needs_redraw(true);
+    }
+
+    pub fn clear_value(&mut self) {
+        self.value = Line::default();
+        self.hint = Line::default();
+        self.set_caret_postion(self.prompt.grapheme_count());
+        self.set_needs_redraw(true);
+    }
+
+    pub fn set_caret_postion(&mut self, col: usize) {
+        self.caret_position.col = col;
+    }
+
+    fn move_left(&mut self) {
+        self.caret_position.col = max(
+            self.caret_position.col.saturating_sub(1),
+            self.prompt.grapheme_count(),
+        );
+    }
+
+    fn move_right(&mut self) {
+        self.caret_position.col = min(
+            self.caret_position.col.saturating_add(1),
+            self.value.grapheme_count() + self.prompt.grapheme_count(),
+        );
+    }
+
+    fn move_word_left(&mut self) {
+        let prompt_len = self.prompt.grapheme_count();
+        let caret_in_value = self.caret_position.col.saturating_sub(prompt_len);
+        self.caret_position.col =
+            prompt_len.saturating_add(self.value.word_start_before(caret_in_value));
+    }
+
+    fn move_word_right(&mut self) {
+        let prompt_len = self.prompt.grapheme_count();
+        let caret_in_value = self.caret_position.col.saturating_sub(prompt_len);
+        self.caret_position.col =
+            prompt_len.saturating_add(self.value.word_end_after(caret_in_value));
+    }
+
+    fn move_to_start_of_line(&mut self) {
+        self.caret_position.col = self.prompt.grapheme_count();
+    }
+
+    fn move_to_end_of_line(&mut self) {
+        self.caret_position.col = self
+            .prompt
+            .grapheme_count()
+            .saturating_add(self.value.grapheme_count());
+    }
+
+    fn insert_char(&mut self, character: char, col: usize) {
+        self.value
+            .insert_char(character, col.saturating_sub(self.prompt.grapheme_count()));
+        self.caret_position.col = col.saturating_add(1);
+    }
+
+    fn delete_char(&mut self, col: usize) {
+        self.value
+            .delete_char(col.saturating_sub(self.prompt.grapheme_count()));
+    }
+
+    // Deletes from the caret up to the column, leaving the caret where it is
+    fn delete_until(&mut self, col: usize) {
+        let at = self
+            .caret_position
+            .col
+            .saturating_sub(self.prompt.grapheme_count());
+        for _ in self.caret_position.col..col {
+            self.value.delete_char(at);
+        }
+    }
+
+    fn delete_char_backward(&mut self, col: usize) {
+        if self.caret_position.col == self.prompt.grapheme_count() {
+            return;
+        }
+
+        self.value.delete_char(
+            col.saturating_sub(self.prompt.grapheme_count())
+                .saturating_sub(1),
+        );
+        self.caret_position.col = col.saturating_sub(1);
+    }
+}
+
+impl UIComponent for CommandBar {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        let area_for_value = self.size.width.saturating_sub(self.prompt.grapheme_count());
+
+        let value_end = self.value.width();
+
+        let value_start = value_end.saturating_sub(area_for_value);
+
+        let message = format!(
+            "{}{}{}",
+            self.prompt,
+            self.value.get_visible_graphemes(value_start..value_end),
+            self.hint
+        );
+
+        // the hint only gets the room the value leaves, whatever doesn't fit is cut off
+        let to_print = Line::from(&message).get_visible_graphemes(0..self.size.width);
+
+        Terminal::print_row_in(origin, self.size.width, &to_print)
+    }
+}
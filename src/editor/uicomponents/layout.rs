@@ -0,0 +1,382 @@
+use super::super::Terminal;
+use super::{UIComponent, View};
+use crate::prelude::*;
+use std::{io::Error, mem};
+
+// A horizontal split stacks its panes on top of each other, a vertical split puts them side by side
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+enum Node {
+    Pane(usize), // index into `Layout::views`
+    Split {
+        direction: SplitDirection,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+}
+
+// The line drawn between the two halves of a split
+struct Divider {
+    direction: SplitDirection,
+    origin: Position,
+    len: usize,
+}
+
+// The panes on screen, arranged as a tree of splits. Every pane shows a view.
+pub struct Layout {
+    root: Node,
+    views: Vec<View>,
+    origins: Vec<Position>, // where each view is placed, relative to the layout
+    dividers: Vec<Divider>,
+    active: usize,
+    size: Size,
+    needs_redraw: bool, // the dividers need to be drawn again
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            root: Node::Pane(0),
+            views: vec![View::default()],
+            origins: vec![Position::default()],
+            dividers: Vec::new(),
+            active: 0,
+            size: Size::default(),
+            needs_redraw: false,
+        }
+    }
+}
+
+impl Layout {
+    pub fn view(&self) -> &View {
+        &self.views[self.active]
+    }
+
+    pub fn view_mut(&mut self) -> &mut View {
+        &mut self.views[self.active]
+    }
+
+    pub fn views(&self) -> impl Iterator<Item = &View> {
+        self.views.iter()
+    }
+
+    // Shows the view in the active pane, in place of the one it showed so far
+    pub fn show(&mut self, view: View) {
+        self.replace_view(self.active, view);
+    }
+
+    // Shows the view in every pane whose view matches the predicate
+    pub fn replace_views_where(&mut self, predicate: impl Fn(&View) -> bool, view: &View) {
+        for idx in 0..self.views.len() {
+            if predicate(&self.views[idx]) {
+                self.replace_view(idx, view.clone());
+            }
+        }
+    }
+
+    fn replace_view(&mut self, idx: usize, view: View) {
+        let size = self.views[idx].get_size();
+        self.views[idx] = view;
+        self.views[idx].resize(size);
+    }
+
+    // Lets the other panes onto the active view's buffer catch up with its changes
+    pub fn sync_views(&mut self) {
+        let (before, rest) = self.views.split_at_mut(self.active);
+        let Some((active, after)) = rest.split_first_mut() else {
+            return;
+        };
+        active.mark_synced();
+
+        for view in before.iter_mut().chain(after.iter_mut()) {
+            if view.shares_buffer_with(active) {
+                view.sync_with_buffer();
+            }
+        }
+    }
+
+    // Splits the active pane in two, both showing the same view. The new pane becomes the active one.
+    pub fn split(&mut self, direction: SplitDirection) {
+        let new_idx = self.views.len();
+        self.views.push(self.views[self.active].clone());
+
+        let active = self.active;
+        Self::replace_pane(&mut self.root, active, &mut |pane| Node::Split {
+            direction,
+            first: Box::new(pane),
+            second: Box::new(Node::Pane(new_idx)),
+        });
+
+        self.active = new_idx;
+        self.set_size(self.size);
+        self.set_needs_redraw(true);
+    }
+
+    // Closes the active pane and gives its room to its neighbour.
+    // Returns false if it's the last pane, which can't be closed.
+    pub fn close_active(&mut self) -> bool {
+        if self.views.len() == 1 {
+            return false;
+        }
+
+        let closed = self.active;
+        let order = self.pane_order();
+        let position = order.iter().position(|&idx| idx == closed).unwrap_or(0);
+        let next = if position > 0 {
+            order[position.saturating_sub(1)]
+        } else {
+            order[1]
+        };
+
+        let root = mem::replace(&mut self.root, Node::Pane(0));
+        self.root = Self::remove_pane(root, closed).unwrap_or(Node::Pane(0));
+        Self::renumber(&mut self.root, closed);
+        self.views.remove(closed);
+        self.active = if next > closed {
+            next.saturating_sub(1)
+        } else {
+            next
+        };
+
+        self.set_size(self.size);
+        self.set_needs_redraw(true);
+        true
+    }
+
+    pub fn focus_next(&mut self) {
+        let order = self.pane_order();
+        let position = order
+            .iter()
+            .position(|&idx| idx == self.active)
+            .unwrap_or(0);
+        self.active = order[position.saturating_add(1) % order.len()];
+    }
+
+    pub fn caret_position(&self) -> Position {
+        let origin = self.origins[self.active];
+        let caret = self.view().caret_position();
+
+        Position {
+            row: origin.row.saturating_add(caret.row),
+            col: origin.col.saturating_add(caret.col),
+        }
+    }
+
+    // Panes from left to right and top to bottom
+    fn pane_order(&self) -> Vec<usize> {
+        fn collect(node: &Node, order: &mut Vec<usize>) {
+            match node {
+                Node::Pane(idx) => order.push(*idx),
+                Node::Split { first, second, .. } => {
+                    collect(first, order);
+                    collect(second, order);
+                }
+            }
+        }
+
+        let mut order = Vec::new();
+        collect(&self.root, &mut order);
+        order
+    }
+
+    fn replace_pane(node: &mut Node, pane: usize, replacement: &mut impl FnMut(Node) -> Node) {
+        match node {
+            Node::Pane(idx) if *idx == pane => {
+                let pane = mem::replace(node, Node::Pane(0));
+                *node = replacement(pane);
+            }
+            Node::Pane(_) => {}
+            Node::Split { first, second, .. } => {
+                Self::replace_pane(first, pane, replacement);
+                Self::replace_pane(second, pane, replacement);
+            }
+        }
+    }
+
+    // Removes the pane from the tree. A split that's left with a single half is replaced by that half.
+    fn remove_pane(node: Node, pane: usize) -> Option<Node> {
+        match node {
+            Node::Pane(idx) if idx == pane => None,
+            Node::Pane(_) => Some(node),
+            Node::Split {
+                direction,
+                first,
+                second,
+            } => match (
+                Self::remove_pane(*first, pane),
+                Self::remove_pane(*second, pane),
+            ) {
+                (Some(first), Some(second)) => Some(Node::Split {
+                    direction,
+                    first: Box::new(first),
+                    second: Box::new(second),
+                }),
+                (Some(remaining), None) | (None, Some(remaining)) => Some(remaining),
+                (None, None) => None,
+            },
+        }
+    }
+
+    // Accounts for the view at `removed` being taken out of `views`
+    fn renumber(node: &mut Node, removed: usize) {
+        match node {
+            Node::Pane(idx) => {
+                if *idx > removed {
+                    *idx = idx.saturating_sub(1);
+                }
+            }
+            Node::Split { first, second, .. } => {
+                Self::renumber(first, removed);
+                Self::renumber(second, removed);
+            }
+        }
+    }
+
+    // Divides the area among the panes of the node, leaving room for a divider between the halves of a split
+    fn arrange(
+        node: &Node,
+        origin: Position,
+        size: Size,
+        panes: &mut Vec<(usize, Position, Size)>,
+        dividers: &mut Vec<Divider>,
+    ) {
+        match node {
+            Node::Pane(idx) => panes.push((*idx, origin, size)),
+            Node::Split {
+                direction,
+                first,
+                second,
+            } => {
+                let total = match direction {
+                    SplitDirection::Horizontal => size.height,
+                    SplitDirection::Vertical => size.width,
+                };
+                let first_len = total.saturating_sub(1).div_ceil(2);
+                let second_len = total.saturating_sub(1).saturating_sub(first_len);
+
+                let (first_size, divider_origin, second_origin, second_size, divider_len) =
+                    match direction {
+                        SplitDirection::Horizontal => (
+                            Size {
+                                height: first_len,
+                                width: size.width,
+                            },
+                            Position {
+                                row: origin.row.saturating_add(first_len),
+                                col: origin.col,
+                            },
+                            Position {
+                                row: origin.row.saturating_add(first_len).saturating_add(1),
+                                col: origin.col,
+                            },
+                            Size {
+                                height: second_len,
+                                width: size.width,
+                            },
+                            size.width,
+                        ),
+                        SplitDirection::Vertical => (
+                            Size {
+                                height: size.height,
+                                width: first_len,
+                            },
+                            Position {
+                                row: origin.row,
+                                col: origin.col.saturating_add(first_len),
+                            },
+                            Position {
+                                row: origin.row,
+                                col: origin.col.saturating_add(first_len).saturating_add(1),
+                            },
+                            Size {
+                                height: size.height,
+                                width: second_len,
+                            },
+                            size.height,
+                        ),
+                    };
+
+                Self::arrange(first, origin, first_size, panes, dividers);
+                dividers.push(Divider {
+                    direction: *direction,
+                    origin: divider_origin,
+                    len: divider_len,
+                });
+                Self::arrange(second, second_origin, second_size, panes, dividers);
+            }
+        }
+    }
+}
+
+impl UIComponent for Layout {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+        for view in &mut self.views {
+            view.set_needs_redraw(value);
+        }
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw || self.views.iter().any(UIComponent::needs_redraw)
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        Self::arrange(
+            &self.root,
+            Position::default(),
+            size,
+            &mut panes,
+            &mut dividers,
+        );
+
+        self.origins = vec![Position::default(); self.views.len()];
+        for (idx, origin, pane_size) in panes {
+            self.origins[idx] = origin;
+            self.views[idx].resize(pane_size);
+        }
+        self.dividers = dividers;
+    }
+
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        for (view, view_origin) in self.views.iter_mut().zip(self.origins.iter()) {
+            view.render(Position {
+                row: origin.row.saturating_add(view_origin.row),
+                col: origin.col.saturating_add(view_origin.col),
+            });
+        }
+
+        if self.needs_redraw {
+            for divider in &self.dividers {
+                let at = Position {
+                    row: origin.row.saturating_add(divider.origin.row),
+                    col: origin.col.saturating_add(divider.origin.col),
+                };
+
+                match divider.direction {
+                    SplitDirection::Horizontal => {
+                        Terminal::print_row_in(at, divider.len, &"─".repeat(divider.len))?;
+                    }
+                    SplitDirection::Vertical => {
+                        for row in 0..divider.len {
+                            let at = Position {
+                                row: at.row.saturating_add(row),
+                                col: at.col,
+                            };
+                            Terminal::print_row_in(at, 1, "│")?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
-mod commandbar;
-mod messagebar;
-mod statusbar;
-mod uicomponent;
-mod view;
-
-pub use commandbar::CommandBar;
-pub use messagebar::MessageBar;
-pub use statusbar::StatusBar;
-pub use uicomponent::UIComponent;
-pub use view::View;
+mod commandbar;
+mod layout;
+mod messagebar;
+mod statusbar;
+mod uicomponent;
+mod view;
+
+pub use commandbar::CommandBar;
+pub use layout::{Layout, SplitDirection};
+pub use messagebar::MessageBar;
+pub use statusbar::StatusBar;
+pub use uicomponent::UIComponent;
+pub use view::{flush_snapshots, View};
@@ -0,0 +1,189 @@
+use std::{
+    fs::{self, File, Metadata, OpenOptions},
+    io::{Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+// A file which is written next to its target and only replaces it once everything made it to disk.
+// Until `commit` is called the target stays untouched, and dropping an uncommitted file removes it again.
+pub struct AtomicFile {
+    file: File,
+    temp_path: PathBuf,
+    target: PathBuf, // the file itself, symlinks resolved
+    in_place: bool,  // the target has other hard links, which replacing it would split off
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(target: &Path) -> Result<Self, Error> {
+        Self::open(target, false)
+    }
+
+    // Like `create`, but only the owner can read the file, whatever the permissions of the target were
+    pub fn create_private(target: &Path) -> Result<Self, Error> {
+        Self::open(target, true)
+    }
+
+    fn open(target: &Path, is_private: bool) -> Result<Self, Error> {
+        // a symlink stays in place, it's the file it points to which gets replaced
+        let target = fs::canonicalize(target).unwrap_or_else(|_| target.to_path_buf());
+        let directory = parent_directory(&target);
+        let file_name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let temp_path = directory.join(format!(".{file_name}.{}.tmp", process::id()));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        if is_private {
+            set_private_mode(&mut options);
+        }
+        let file = options.open(&temp_path).map_err(|err| {
+            with_context(
+                &err,
+                &format!("can't create a temporary file in {}", directory.display()),
+            )
+        })?;
+
+        let atomic_file = Self {
+            file,
+            temp_path,
+            in_place: has_other_links(&target),
+            target,
+            committed: false,
+        };
+
+        // keep the original's permissions, a fresh file would get the default ones
+        let original = if is_private {
+            None
+        } else {
+            fs::metadata(&atomic_file.target).ok()
+        };
+        if let Some(metadata) = original {
+            fs::set_permissions(&atomic_file.temp_path, metadata.permissions()).map_err(|err| {
+                with_context(&err, "can't copy the permissions of the original file")
+            })?;
+        }
+
+        Ok(atomic_file)
+    }
+
+    // Makes sure the contents are on disk, then moves them over the target and returns its metadata.
+    // With `keep_backup` set, the previous version of the target is kept as `<target>~`.
+    pub fn commit(mut self, keep_backup: bool) -> Result<Metadata, Error> {
+        self.file
+            .flush()
+            .and_then(|()| self.file.sync_all())
+            .map_err(|err| with_context(&err, "can't write the file to disk"))?;
+
+        if keep_backup && self.target.exists() {
+            let mut backup = self.target.clone().into_os_string();
+            backup.push("~");
+            fs::copy(&self.target, &backup)
+                .map_err(|err| with_context(&err, "can't create the backup file"))?;
+        }
+
+        if self.in_place {
+            // the contents are safely on disk by now, copying them over keeps every name of the file
+            fs::copy(&self.temp_path, &self.target)
+                .and_then(|_| File::open(&self.target)?.sync_all())
+                .map_err(|err| with_context(&err, "can't overwrite the original file"))?;
+            let _ = fs::remove_file(&self.temp_path);
+        } else {
+            fs::rename(&self.temp_path, &self.target)
+                .map_err(|err| with_context(&err, "can't replace the original file"))?;
+        }
+        self.committed = true;
+
+        // the rename itself only survives a crash once the directory is synced as well
+        if let Ok(directory) = File::open(parent_directory(&self.target)) {
+            let _ = directory.sync_all();
+        }
+
+        fs::metadata(&self.target)
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.file
+            .write(buf)
+            .map_err(|err| with_context(&err, "can't write the file"))
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_private_mode(options: &mut OpenOptions) {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn set_private_mode(_options: &mut OpenOptions) {}
+
+#[cfg(unix)]
+fn has_other_links(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.nlink() > 1)
+}
+
+#[cfg(not(unix))]
+fn has_other_links(_path: &Path) -> bool {
+    false
+}
+
+fn parent_directory(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+// Describes what went wrong in words the user can act on, keeping the kind of the original error
+fn with_context(err: &Error, action: &str) -> Error {
+    let reason = match err.kind() {
+        ErrorKind::PermissionDenied => "permission denied".to_string(),
+        ErrorKind::NotFound => "the directory doesn't exist".to_string(),
+        _ => os_reason(err).map_or_else(|| err.to_string(), str::to_string),
+    };
+    Error::new(err.kind(), format!("{action}: {reason}"))
+}
+
+// The kinds for these are too new for our minimum Rust version, so they're told apart by their errno
+#[cfg(unix)]
+fn os_reason(err: &Error) -> Option<&'static str> {
+    const EISDIR: i32 = 21;
+    const ENOSPC: i32 = 28;
+    const EROFS: i32 = 30;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const EDQUOT: i32 = 122;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const EDQUOT: i32 = 69;
+
+    match err.raw_os_error()? {
+        ENOSPC => Some("the disk is full"),
+        EDQUOT => Some("the disk quota is exceeded"),
+        EROFS => Some("the file system is read-only"),
+        EISDIR => Some("the path is a directory"),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn os_reason(_err: &Error) -> Option<&'static str> {
+    None
+}
@@ -1,180 +1,1002 @@
-use super::super::super::AnnotatedString;
-use super::{FileInfo, Highlighter, Line};
-use crate::prelude::*;
-use std::{
-    fs::{read_to_string, File},
-    io::{Error, Write},
-    ops::Range,
-};
-#[derive(Default)]
-pub struct Buffer {
-    lines: Vec<Line>,    // vector of lines in the buffer, including the whole document
-    file_info: FileInfo, // file info of the document in the current buffer
-    dirty: bool, // to indicate whether the buffer is modified or not, default is false, set to true when buffer is modified
-}
-
-impl Buffer {
-    pub const fn is_dirty(&self) -> bool {
-        self.dirty
-    }
-
-    pub const fn get_file_info(&self) -> &FileInfo {
-        &self.file_info
-    }
-
-    pub fn len(&self) -> usize {
-        self.lines.len()
-    }
-
-    pub fn grapheme_count(&self, idx: LineIdx) -> GraphemeIdx {
-        self.lines.get(idx).map_or(0, Line::grapheme_count)
-    }
-
-    pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx) -> GraphemeIdx {
-        self.lines
-            .get(idx)
-            .map_or(0, |line| line.width_until(until))
-    }
-
-    pub fn get_highlighted_substring(
-        &self,
-        line_idx: LineIdx,
-        range: Range<GraphemeIdx>,
-        highlighter: &Highlighter,
-    ) -> Option<AnnotatedString> {
-        self.lines.get(line_idx).map(|line| {
-            line.get_annotated_visible_substr(range, Some(&highlighter.get_annotations(line_idx)))
-        })
-    }
-
-    pub fn highlight(
-        &self,
-        idx: LineIdx,
-        search_results: &Option<Vec<GraphemeIdx>>,
-        highlighter: &mut Highlighter,
-    ) {
-        if let Some(line) = self.lines.get(idx) {
-            highlighter.highlight(idx, line, search_results);
-        }
-    }
-
-    pub fn load_file(file_name: &str) -> Result<Self, Error> {
-        let contents = read_to_string(file_name)?;
-        let mut lines = Vec::new();
-
-        for value in contents.lines() {
-            lines.push(Line::from(value));
-        }
-
-        Ok(Self {
-            lines,
-            file_info: FileInfo::from(file_name),
-            dirty: false,
-        })
-    }
-
-    pub fn search(&mut self, query: &str) -> Option<Vec<Location>> {
-        let mut locations = Vec::new();
-
-        for (line_index, line) in self.lines.iter().enumerate() {
-            if let Some(grapheme_indices) = line.search(query) {
-                for grapheme_index in grapheme_indices {
-                    locations.push(Location {
-                        line_index,
-                        grapheme_index,
-                    });
-                }
-            }
-        }
-
-        if locations.is_empty() {
-            None
-        } else {
-            Some(locations)
-        }
-    }
-
-    fn save_to_file(&self, file_info: &FileInfo) -> Result<(), Error> {
-        if let Some(file_path) = &file_info.get_path() {
-            let mut file = File::create(file_path)?;
-
-            for line in &self.lines {
-                writeln!(file, "{line}")?;
-            }
-        }
-
-        Ok(())
-    }
-
-    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        let file_info = FileInfo::from(file_name);
-        self.save_to_file(&file_info)?;
-        self.file_info = file_info;
-        self.dirty = false;
-        Ok(())
-    }
-
-    pub fn save(&mut self) -> Result<(), Error> {
-        self.save_to_file(&self.file_info)?;
-        self.dirty = false;
-        Ok(())
-    }
-    pub fn is_empty(&self) -> bool {
-        self.lines.is_empty()
-    }
-
-    pub const fn is_file_loaded(&self) -> bool {
-        self.file_info.has_path()
-    }
-
-    pub fn height(&self) -> usize {
-        self.lines.len()
-    }
-
-    pub fn insert_char(&mut self, character: char, at: Location) {
-        if at.line_index > self.height() {
-            return;
-        }
-
-        if at.line_index == self.height() {
-            self.lines.push(Line::from(&character.to_string()));
-            self.dirty = true;
-        } else if let Some(line) = self.lines.get_mut(at.line_index) {
-            line.insert_char(character, at.grapheme_index);
-            self.dirty = true;
-        }
-    }
-
-    pub fn delete_char(&mut self, at: Location) {
-        if let Some(line) = self.lines.get(at.line_index) {
-            if at.grapheme_index >= line.grapheme_count()
-                && self.height() > at.line_index.saturating_add(1)
-            {
-                // to check if we are at the end of the line
-                let next_line = self.lines.remove(at.line_index.saturating_add(1));
-
-                #[allow(clippy::integer_arithmetic)]
-                self.lines[at.line_index].append(&next_line);
-                self.dirty = true;
-            } else if at.grapheme_index < line.grapheme_count() {
-                #[allow(clippy::integer_arithmetic)]
-                self.lines[at.line_index].delete_char(at.grapheme_index);
-                self.dirty = true;
-            }
-        }
-    }
-
-    pub fn insert_new_line(&mut self, at: Location) {
-        if at.line_index == self.height() {
-            // if we are at the end of the document,
-            // which means we are at the last line,
-            // insert a new empty line
-            self.lines.push(Line::default());
-            self.dirty = true;
-        } else if let Some(line) = self.lines.get_mut(at.line_index) {
-            let new = line.split(at.grapheme_index);
-            self.lines.insert(at.line_index.saturating_add(1), new);
-            self.dirty = true;
-        }
-    }
-}
+use super::super::super::{AnnotatedString, Encoding, LineEnding};
+use super::{
+    history::{ChangeKind, History, Operation, TextPosition},
+    AtomicFile, DiskState, FileInfo, Highlighter, Line, SwapFile, SyntaxState, TabStops,
+};
+use crate::prelude::*;
+use regex::Regex;
+use ropey::Rope;
+use std::{
+    cell::RefCell,
+    cmp::min,
+    fs::{metadata, read},
+    io::{Error, ErrorKind, Write},
+    iter::repeat_n,
+    ops::Range,
+    rc::Rc,
+};
+// How many lines away from a bracket its partner is looked for
+const BRACKET_SEARCH_LINES: usize = 1000;
+
+#[derive(Default)]
+pub struct Buffer {
+    text: Rope, // the whole document, every line in it (including the last one) ends with a line break
+    file_info: FileInfo, // file info of the document in the current buffer
+    history: History, // record of every edit, used for undo/redo and to tell whether the buffer is modified
+    saved_line_ending: LineEnding, // line ending of the file on disk, the document is modified when it's converted
+    swap_file: Option<SwapFile>, // copy of the unsaved changes, only kept for files which can be saved
+    tab_stops: TabStops,
+    lines: RefCell<Vec<Option<Rc<Line>>>>, // lines built so far, by index, dropped once an edit touches them
+    syntax_states: RefCell<Vec<SyntaxState>>, // the state each line starts in, up to the first line not highlighted since the last edit
+    changes: Vec<TextChange>, // the edits not every view onto the buffer has followed yet
+    forgotten_changes: usize, // how many edits came before those
+}
+
+// An edit replaced the text from `start` to `removed_end` with the text from `start` to `inserted_end`
+#[derive(Clone, Copy)]
+pub struct TextChange {
+    start: Location,
+    removed_end: Location,
+    inserted_end: Location,
+}
+
+impl TextChange {
+    // Where a location ends up after the edit. Locations in the text which was removed end up at its start.
+    pub fn shift(&self, location: Location) -> Location {
+        if location <= self.start {
+            location
+        } else if location < self.removed_end {
+            self.start
+        } else if location.line_index == self.removed_end.line_index {
+            Location {
+                line_index: self.inserted_end.line_index,
+                grapheme_index: location
+                    .grapheme_index
+                    .saturating_sub(self.removed_end.grapheme_index)
+                    .saturating_add(self.inserted_end.grapheme_index),
+            }
+        } else {
+            Location {
+                line_index: location
+                    .line_index
+                    .saturating_sub(self.removed_end.line_index)
+                    .saturating_add(self.inserted_end.line_index),
+                grapheme_index: location.grapheme_index,
+            }
+        }
+    }
+}
+
+impl Buffer {
+    pub fn is_dirty(&self) -> bool {
+        self.history.is_dirty()
+            || self.file_info.get_line_ending() != self.saved_line_ending
+            || self.file_info.has_mixed_line_endings()
+    }
+
+    pub const fn get_file_info(&self) -> &FileInfo {
+        &self.file_info
+    }
+
+    pub fn len(&self) -> usize {
+        self.height()
+    }
+
+    pub fn grapheme_count(&self, idx: LineIdx) -> GraphemeIdx {
+        self.line(idx).map_or(0, |line| line.grapheme_count())
+    }
+
+    pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx) -> GraphemeIdx {
+        self.line(idx).map_or(0, |line| line.width_until(until))
+    }
+
+    // Where the rows of the line start when it's wrapped to the width, see `Line::wrap`
+    pub fn wrap(&self, idx: LineIdx, width: ColIdx) -> Vec<GraphemeIdx> {
+        self.line(idx)
+            .map_or_else(|| vec![0], |line| line.wrap(width))
+    }
+
+    pub fn word_start_before(&self, idx: LineIdx, from: GraphemeIdx) -> GraphemeIdx {
+        self.line(idx)
+            .map_or(0, |line| line.word_start_before(from))
+    }
+
+    pub fn word_end_after(&self, idx: LineIdx, from: GraphemeIdx) -> GraphemeIdx {
+        self.line(idx).map_or(0, |line| line.word_end_after(from))
+    }
+
+    // Whether the line holds nothing but whitespace, which is what separates paragraphs
+    pub fn is_blank_line(&self, idx: LineIdx) -> bool {
+        self.line(idx).is_none_or(|line| line.trim().is_empty())
+    }
+
+    pub fn is_bracket_at(&self, at: Location) -> bool {
+        self.bracket_at(at).is_some()
+    }
+
+    // The bracket at the location along with its partner, the bracket opening or closing it,
+    // and whether that partner comes after it
+    fn bracket_at(&self, at: Location) -> Option<(char, char, bool)> {
+        let line = self.line(at.line_index)?;
+        let byte_idx = line.grapheme_idx_to_byte_idx(at.grapheme_index);
+        match line.get(byte_idx..)?.chars().next()? {
+            '(' => Some(('(', ')', true)),
+            '[' => Some(('[', ']', true)),
+            '{' => Some(('{', '}', true)),
+            ')' => Some((')', '(', false)),
+            ']' => Some((']', '[', false)),
+            '}' => Some(('}', '{', false)),
+            _ => None,
+        }
+    }
+
+    // Finds the bracket opening or closing the one at the location, up to `BRACKET_SEARCH_LINES` away.
+    // Brackets in comments and strings are skipped, as far as the syntax highlighting for the file type
+    // can tell them apart.
+    pub fn find_matching_bracket(&self, at: Location) -> Option<Location> {
+        let (bracket, partner, forward) = self.bracket_at(at)?;
+        let mut highlighter =
+            Highlighter::new(self.file_info.get_file_type(), false, None, None, None);
+
+        // brackets are collected in the order they are met
+        let mut depth: usize = 0;
+        let mut brackets_before = Vec::new();
+        let (first_line, last_line) = if forward {
+            (
+                at.line_index,
+                min(
+                    at.line_index.saturating_add(BRACKET_SEARCH_LINES),
+                    self.height().saturating_sub(1),
+                ),
+            )
+        } else {
+            (
+                at.line_index.saturating_sub(BRACKET_SEARCH_LINES),
+                at.line_index,
+            )
+        };
+
+        // usually the lines on screen were highlighted already, so the state the first line starts in is known
+        self.start_highlighting(first_line, &mut highlighter);
+
+        for line_idx in first_line..=last_line {
+            let Some(line) = self.peek_line(line_idx) else {
+                break;
+            };
+            highlighter.highlight(line_idx, &line, &None);
+
+            let mut to_grapheme_idx = line.grapheme_idx_walker();
+            for (byte_idx, character) in line.char_indices() {
+                if (character != bracket && character != partner)
+                    || !highlighter.is_code(line_idx, byte_idx)
+                {
+                    continue;
+                }
+
+                let location = Location {
+                    line_index: line_idx,
+                    grapheme_index: to_grapheme_idx(byte_idx),
+                };
+
+                if !forward {
+                    if location > at {
+                        break;
+                    }
+                    brackets_before.push((location, character == bracket));
+                } else if location >= at {
+                    // the first bracket counted has to be the one at the location, otherwise
+                    // that one is in a comment or string
+                    if depth == 0 && location != at {
+                        return None;
+                    }
+                    depth = if character == bracket {
+                        depth.saturating_add(1)
+                    } else {
+                        depth.saturating_sub(1)
+                    };
+                    if depth == 0 {
+                        return Some(location);
+                    }
+                }
+            }
+        }
+
+        // going backwards, the last bracket collected has to be the one at the location,
+        // otherwise it's in a comment or string
+        if brackets_before.last().map(|(location, _)| *location) != Some(at) {
+            return None;
+        }
+
+        for (location, is_same_bracket) in brackets_before.into_iter().rev() {
+            depth = if is_same_bracket {
+                depth.saturating_add(1)
+            } else {
+                depth.saturating_sub(1)
+            };
+            if depth == 0 {
+                return Some(location);
+            }
+        }
+
+        None
+    }
+
+    pub fn grapheme_at_col(
+        &self,
+        idx: LineIdx,
+        from: GraphemeIdx,
+        to: GraphemeIdx,
+        col: ColIdx,
+    ) -> GraphemeIdx {
+        self.line(idx)
+            .map_or(from, |line| line.grapheme_at_col(from, to, col))
+    }
+
+    pub fn get_highlighted_substring(
+        &self,
+        line_idx: LineIdx,
+        range: Range<GraphemeIdx>,
+        highlighter: &Highlighter,
+    ) -> Option<AnnotatedString> {
+        self.line(line_idx).map(|line| {
+            line.get_annotated_visible_substr(range, Some(&highlighter.get_annotations(line_idx)))
+        })
+    }
+
+    // Lines have to be highlighted in order, starting with the one passed to `start_highlighting`
+    pub fn highlight(
+        &self,
+        idx: LineIdx,
+        search_results: &Option<Vec<Range<GraphemeIdx>>>,
+        highlighter: &mut Highlighter,
+    ) {
+        if let Some(line) = self.line(idx) {
+            highlighter.highlight(idx, &line, search_results);
+
+            let mut syntax_states = self.syntax_states.borrow_mut();
+            if highlighter.has_syntax() && syntax_states.len() == idx.saturating_add(1) {
+                syntax_states.push(highlighter.syntax_state());
+            }
+        }
+    }
+
+    // Gets the syntax highlighter to the state the line starts in. Only the lines after the last one
+    // whose state is known have to be gone through, and only once until they are edited.
+    pub fn start_highlighting(&self, idx: LineIdx, highlighter: &mut Highlighter) {
+        if !highlighter.has_syntax() {
+            return;
+        }
+
+        let idx = min(idx, self.height());
+        let mut syntax_states = self.syntax_states.borrow_mut();
+        if syntax_states.is_empty() {
+            syntax_states.push(SyntaxState::default());
+        }
+
+        while syntax_states.len() <= idx {
+            let line_idx = syntax_states.len().saturating_sub(1);
+            let (Some(&state), Some(line)) = (syntax_states.last(), self.peek_line(line_idx))
+            else {
+                break;
+            };
+            highlighter.set_syntax_state(state);
+            highlighter.highlight(line_idx, &line, &None);
+            syntax_states.push(highlighter.syntax_state());
+        }
+
+        if let Some(&state) = syntax_states.get(idx) {
+            highlighter.set_syntax_state(state);
+        }
+    }
+
+    // The line at the given index, without its line break.
+    // Lines are only built on demand and kept until they are edited, so grapheme and width information
+    // is computed for the lines in use only, and only once.
+    fn line(&self, idx: LineIdx) -> Option<Rc<Line>> {
+        if idx >= self.height() {
+            return None;
+        }
+
+        let mut lines = self.lines.borrow_mut();
+        if lines.len() != self.height() {
+            // edits keep the lines in step with the text, this only happens before the first line is built
+            lines.clear();
+            lines.resize(self.height(), None);
+        }
+
+        let line = lines
+            .get_mut(idx)?
+            .get_or_insert_with(|| Rc::new(self.build_line(idx)));
+        Some(Rc::clone(line))
+    }
+
+    // Like `line`, but a line which wasn't built yet isn't kept, for going through the whole document
+    fn peek_line(&self, idx: LineIdx) -> Option<Rc<Line>> {
+        if idx >= self.height() {
+            return None;
+        }
+
+        self.lines
+            .borrow()
+            .get(idx)
+            .and_then(Clone::clone)
+            .or_else(|| Some(Rc::new(self.build_line(idx))))
+    }
+
+    fn build_line(&self, idx: LineIdx) -> Line {
+        let mut string = String::from(self.text.line(idx));
+        string.pop();
+
+        Line::with_tab_stops(&string, self.tab_stops)
+    }
+
+    // An edit replaced `removed` lines starting at the given one with `added` new ones
+    fn forget_lines(&self, first: LineIdx, removed: usize, added: usize) {
+        let mut lines = self.lines.borrow_mut();
+        let end = min(first.saturating_add(removed), lines.len());
+        lines.splice(min(first, end)..end, repeat_n(None, added));
+
+        // the line starts in the same state as before, the lines after might not
+        self.syntax_states
+            .borrow_mut()
+            .truncate(first.saturating_add(1));
+    }
+
+    pub fn set_tab_stops(&mut self, tab_stops: TabStops) {
+        self.tab_stops = tab_stops;
+        self.lines.borrow_mut().clear();
+    }
+
+    pub fn load_file(file_name: &str) -> Result<Self, Error> {
+        let bytes = read(file_name)?;
+        let mut file_info = FileInfo::from(file_name);
+        file_info.set_disk_state(Some(DiskState::from(&bytes, &metadata(file_name)?)));
+
+        let decoded = Encoding::decode(bytes);
+        let mut contents = decoded.text;
+
+        file_info.set_encoding(decoded.encoding);
+        file_info.set_read_only(decoded.is_binary);
+
+        // Internally every line ends with a single '\n', the original format is restored on save
+        file_info.detect_format(&contents);
+
+        if file_info.has_bom() {
+            contents.remove(0);
+        }
+
+        if contents.contains('\r') {
+            contents = contents.replace("\r\n", "\n");
+        }
+
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+
+        let swap_file = if file_info.is_read_only() {
+            None
+        } else {
+            file_info.get_path().map(SwapFile::for_document)
+        };
+
+        Ok(Self {
+            text: Rope::from_str(&contents),
+            saved_line_ending: file_info.get_line_ending(),
+            file_info,
+            history: History::default(),
+            swap_file,
+            tab_stops: TabStops::default(),
+            lines: RefCell::default(),
+            syntax_states: RefCell::default(),
+            changes: Vec::new(),
+            forgotten_changes: 0,
+        })
+    }
+
+    pub fn search(&mut self, pattern: &Regex) -> Option<Vec<Range<Location>>> {
+        let mut locations = Vec::new();
+
+        for line_index in 0..self.height() {
+            let Some(line) = self.peek_line(line_index) else {
+                break;
+            };
+
+            if let Some(ranges) = line.search(pattern) {
+                for range in ranges {
+                    locations.push(
+                        Location {
+                            line_index,
+                            grapheme_index: range.start,
+                        }..Location {
+                            line_index,
+                            grapheme_index: range.end,
+                        },
+                    );
+                }
+            }
+        }
+
+        if locations.is_empty() {
+            None
+        } else {
+            Some(locations)
+        }
+    }
+
+    // Writes the document and returns what the file looks like on disk now
+    fn save_to_file(
+        &self,
+        file_info: &FileInfo,
+        keep_backup: bool,
+    ) -> Result<Option<DiskState>, Error> {
+        if file_info.is_read_only() {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "the file doesn't look like text and was opened read-only",
+            ));
+        }
+
+        let Some(file_path) = file_info.get_path() else {
+            return Ok(None);
+        };
+
+        let encoding = file_info.get_encoding();
+        let len = self.text.len_chars();
+        let contents = if file_info.has_final_newline() {
+            self.text.slice(..)
+        } else {
+            self.text.slice(..len.saturating_sub(1))
+        };
+
+        // everything is encoded before touching the file, so a character the encoding lacks can't cut it short
+        let mut bytes = Vec::new();
+
+        if file_info.has_bom() {
+            encoding.write(&mut bytes, "\u{feff}")?;
+        }
+
+        let line_ending = file_info.get_line_ending();
+        for chunk in contents.chunks() {
+            match line_ending {
+                LineEnding::Lf => encoding.write(&mut bytes, chunk)?,
+                LineEnding::Crlf => {
+                    encoding.write(&mut bytes, &chunk.replace('\n', line_ending.as_str()))?;
+                }
+            }
+        }
+
+        // the original file is only replaced once the new contents are safely on disk
+        let mut file = AtomicFile::create(file_path)?;
+        file.write_all(&bytes)?;
+        let metadata = file.commit(keep_backup)?;
+
+        // no need to read the file back, what's on disk is what was just written
+        Ok(Some(DiskState::from(&bytes, &metadata)))
+    }
+
+    // Tells whether another program wrote to the file since we loaded or saved it
+    pub fn has_changed_on_disk(&self) -> bool {
+        self.file_info.has_changed_on_disk()
+    }
+
+    pub fn toggle_line_ending(&mut self) -> LineEnding {
+        let line_ending = self.file_info.get_line_ending().toggled();
+        self.file_info.set_line_ending(line_ending);
+        line_ending
+    }
+
+    pub fn save_as(&mut self, file_name: &str, keep_backup: bool) -> Result<(), Error> {
+        let mut file_info = self.file_info.renamed(file_name);
+        let disk_state = self.save_to_file(&file_info, keep_backup)?;
+        file_info.set_disk_state(disk_state);
+        self.file_info = file_info;
+
+        // the swap file belongs next to the file the document is saved to now
+        self.remove_swap();
+        self.swap_file = self.file_info.get_path().map(SwapFile::for_document);
+        self.history.mark_saved();
+        self.saved_line_ending = self.file_info.get_line_ending();
+        Ok(())
+    }
+
+    pub fn save(&mut self, keep_backup: bool) -> Result<(), Error> {
+        let disk_state = self.save_to_file(&self.file_info, keep_backup)?;
+        self.file_info.set_disk_state(disk_state);
+        self.file_info.clear_mixed_line_endings();
+        self.history.mark_saved();
+        self.saved_line_ending = self.file_info.get_line_ending();
+        Ok(())
+    }
+    // SECTION: swap file
+
+    // Keeps the swap file in line with the document, or removes it once there's nothing left to recover
+    pub fn update_swap(&mut self, is_idle: bool) -> Result<(), Error> {
+        let is_dirty = self.is_dirty();
+        let Some(swap_file) = &mut self.swap_file else {
+            return Ok(());
+        };
+
+        if is_dirty {
+            swap_file.update(&self.text, self.history.revision(), is_idle)
+        } else {
+            swap_file.remove();
+            Ok(())
+        }
+    }
+
+    pub fn remove_swap(&mut self) {
+        if let Some(swap_file) = &mut self.swap_file {
+            swap_file.remove();
+        }
+    }
+
+    pub fn has_leftover_swap(&self) -> bool {
+        self.swap_file.as_ref().is_some_and(SwapFile::has_leftover)
+    }
+
+    // Replaces the document with the contents of the leftover swap file, as a single change which can be undone
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        let Some(swap_file) = &mut self.swap_file else {
+            return Ok(());
+        };
+
+        let mut contents = swap_file.read_leftover()?;
+        swap_file.recovered();
+
+        if contents.ends_with('\n') {
+            contents.pop();
+        }
+
+        let end_of_document = Location {
+            line_index: self.height(),
+            grapheme_index: 0,
+        };
+        self.replace(Location::default()..end_of_document, &contents);
+
+        Ok(())
+    }
+
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        match &mut self.swap_file {
+            Some(swap_file) => swap_file.discard_leftover(),
+            None => Ok(()),
+        }
+    }
+
+    // Describes which lines of the leftover swap file differ from the file on disk
+    pub fn swap_diff(&self) -> Result<String, Error> {
+        let Some(swap_file) = &self.swap_file else {
+            return Ok(String::new());
+        };
+
+        let swap_contents = swap_file.read_leftover()?;
+        let swap_lines: Vec<&str> = swap_contents.lines().collect();
+        let file_lines: Vec<String> = (0..self.height())
+            .map(|idx| {
+                String::from(self.text.line(idx))
+                    .trim_end_matches('\n')
+                    .to_string()
+            })
+            .collect();
+
+        let common_prefix = swap_lines
+            .iter()
+            .zip(file_lines.iter())
+            .take_while(|(swap_line, file_line)| *swap_line == file_line)
+            .count();
+        let common_suffix = swap_lines[common_prefix..]
+            .iter()
+            .rev()
+            .zip(file_lines[common_prefix..].iter().rev())
+            .take_while(|(swap_line, file_line)| *swap_line == file_line)
+            .count();
+
+        let common = common_prefix.saturating_add(common_suffix);
+        let swap_changed = swap_lines.len().saturating_sub(common);
+        let file_changed = file_lines.len().saturating_sub(common);
+
+        if swap_changed == 0 && file_changed == 0 {
+            return Ok("The swap file matches the file on disk.".to_string());
+        }
+
+        Ok(format!(
+            "From line {}, {swap_changed} line(s) in the swap file replace {file_changed} line(s) on disk.",
+            common_prefix.saturating_add(1)
+        ))
+    }
+
+    // END SECTION
+
+    pub fn is_empty(&self) -> bool {
+        self.text.len_chars() == 0
+    }
+
+    // How many edits were made to the buffer so far, a reload counts as one nobody can follow
+    pub fn change_count(&self) -> usize {
+        self.forgotten_changes.saturating_add(self.changes.len())
+    }
+
+    // The edits made after the first `count` ones, None if some of them were forgotten already
+    pub fn changes_since(&self, count: usize) -> Option<&[TextChange]> {
+        count
+            .checked_sub(self.forgotten_changes)
+            .and_then(|idx| self.changes.get(idx..))
+    }
+
+    // Drops the first `count` edits, once every view onto the buffer has followed them
+    pub fn forget_changes(&mut self, count: usize) {
+        let forgotten = count
+            .saturating_sub(self.forgotten_changes)
+            .min(self.changes.len());
+        self.changes.drain(..forgotten);
+        self.forgotten_changes = self.forgotten_changes.saturating_add(forgotten);
+    }
+
+    // Takes the place of this buffer, e.g. when the file is reloaded. The edits keep being counted,
+    // with the reload as one more, so a view which followed the old buffer knows it can't follow.
+    pub fn replace_with(&mut self, mut buffer: Self) {
+        buffer.forgotten_changes = self.change_count().saturating_add(1);
+        *self = buffer;
+    }
+
+    pub const fn is_read_only(&self) -> bool {
+        self.file_info.is_read_only()
+    }
+
+    pub const fn has_mixed_line_endings(&self) -> bool {
+        self.file_info.has_mixed_line_endings()
+    }
+
+    pub const fn is_file_loaded(&self) -> bool {
+        self.file_info.has_path()
+    }
+
+    pub fn height(&self) -> usize {
+        // the line after the final line break is the empty line past the end of the document
+        self.text.len_lines().saturating_sub(1)
+    }
+
+    pub fn insert_char(&mut self, character: char, at: Location) {
+        if at.line_index > self.height() {
+            return;
+        }
+
+        self.insert(at, &character.to_string(), ChangeKind::Typing);
+    }
+
+    pub fn delete_char(&mut self, at: Location) {
+        if let Some(end) = self.location_after(at) {
+            self.delete(at, end, ChangeKind::Deletion);
+        }
+    }
+
+    // Deletes the grapheme, or the line break, right before the given location.
+    pub fn delete_char_backward(&mut self, at: Location) {
+        if let Some(start) = self.location_before(at) {
+            self.delete(start, at, ChangeKind::BackwardDeletion);
+        }
+    }
+
+    pub fn insert_new_line(&mut self, at: Location) {
+        if at.line_index > self.height() {
+            return;
+        }
+
+        // On the line past the end of the document, inserting the line itself is all we need
+        let text = if at.line_index == self.height() {
+            ""
+        } else {
+            "\n"
+        };
+
+        self.insert(at, text, ChangeKind::Other);
+    }
+
+    pub fn get_text(&self, range: Range<Location>) -> String {
+        let range = self.clamp_range(range);
+        let start = self.to_char_idx(self.to_text_position(range.start));
+        let end = self.to_char_idx(self.to_text_position(range.end));
+
+        self.text.slice(start..end).to_string()
+    }
+
+    // Inserts the text, which may span multiple lines, and returns the location right after it
+    pub fn insert_text(&mut self, text: &str, at: Location) -> Location {
+        if at.line_index > self.height() {
+            return at;
+        }
+
+        self.insert(at, text, ChangeKind::Other)
+    }
+
+    pub fn delete_range(&mut self, range: Range<Location>) {
+        self.delete(range.start, range.end, ChangeKind::Other);
+    }
+
+    // Replaces a match found by `search`. References like `$1` or `${name}` in the replacement are
+    // filled in from the capture groups. Returns the location right after the inserted text,
+    // or None if the pattern doesn't match there anymore.
+    pub fn replace_match(
+        &mut self,
+        range: Range<Location>,
+        pattern: &Regex,
+        replacement: &str,
+    ) -> Option<Location> {
+        if range.start.line_index != range.end.line_index {
+            return None;
+        }
+
+        let line = self.line(range.start.line_index)?;
+        let start = line.grapheme_idx_to_byte_idx(range.start.grapheme_index);
+        let end = line.grapheme_idx_to_byte_idx(range.end.grapheme_index);
+
+        let captures = pattern.captures_at(&line, start)?;
+        let found = captures.get(0)?;
+        if found.start() != start || found.end() != end {
+            return None;
+        }
+
+        let mut text = String::new();
+        captures.expand(replacement, &mut text);
+        Some(self.replace(range, &text))
+    }
+
+    // Every change until `end_change_group` is called is undone in a single step
+    pub fn begin_change_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    pub fn end_change_group(&mut self) {
+        self.history.end_group();
+    }
+
+    // Replaces the range with the text as a single undo step and returns the location right after the new text
+    pub fn replace(&mut self, range: Range<Location>, text: &str) -> Location {
+        self.history.begin_group();
+        self.delete_range(range.clone());
+        let end = if text.is_empty() {
+            range.start
+        } else {
+            self.insert_text(text, range.start)
+        };
+        self.history.end_group();
+        end
+    }
+
+    // Reverts the last change and returns the caret location from before it was made
+    pub fn undo(&mut self) -> Option<Location> {
+        let (operations, caret) = self.history.undo()?;
+        self.apply_all(&operations);
+        Some(caret)
+    }
+
+    // Reapplies the last undone change and returns the caret location from after it was made
+    pub fn redo(&mut self) -> Option<Location> {
+        let (operations, caret) = self.history.redo()?;
+        self.apply_all(&operations);
+        Some(caret)
+    }
+
+    // SECTION: recorded edits
+
+    fn insert(&mut self, at: Location, text: &str, kind: ChangeKind) -> Location {
+        let (position, text) = if at.line_index >= self.height() {
+            // Text inserted past the last line ends up on a line of its own
+            let last_line_index = self.height().saturating_sub(1);
+            self.line(last_line_index).map_or_else(
+                || (TextPosition::default(), text.to_string()),
+                |line| {
+                    (
+                        TextPosition {
+                            line_index: last_line_index,
+                            byte_index: line.len(),
+                        },
+                        format!("\n{text}"),
+                    )
+                },
+            )
+        } else {
+            (self.to_text_position(at), text.to_string())
+        };
+
+        // every line ends with a line break, so the first text in an empty document brings its own
+        let caret_after = Self::end_of_text(position, &text);
+        let text = if self.is_empty() && !text.ends_with('\n') {
+            format!("{text}\n")
+        } else {
+            text
+        };
+
+        self.apply_insert(position, &text);
+        let caret_after = self.to_location(caret_after);
+
+        self.history.record(
+            Operation::Insert { at: position, text },
+            kind,
+            at,
+            caret_after,
+        );
+
+        caret_after
+    }
+
+    fn delete(&mut self, start: Location, end: Location, kind: ChangeKind) {
+        let Range { start, end } = self.clamp_range(start..end);
+        let start_position = self.to_text_position(start);
+        let end_position = self.to_text_position(end);
+        let text = self.apply_delete(start_position, end_position);
+
+        if text.is_empty() {
+            return;
+        }
+
+        let caret_before = if kind == ChangeKind::BackwardDeletion {
+            end
+        } else {
+            start
+        };
+
+        self.history.record(
+            Operation::Delete {
+                at: start_position,
+                text,
+            },
+            kind,
+            caret_before,
+            start,
+        );
+    }
+
+    // Returns the location right after the grapheme or line break at the given location, if any
+    fn location_after(&self, at: Location) -> Option<Location> {
+        let line = self.line(at.line_index)?;
+
+        if at.grapheme_index < line.grapheme_count() {
+            Some(Location {
+                line_index: at.line_index,
+                grapheme_index: at.grapheme_index.saturating_add(1),
+            })
+        } else if at.line_index.saturating_add(1) < self.height() {
+            Some(Location {
+                line_index: at.line_index.saturating_add(1),
+                grapheme_index: 0,
+            })
+        } else {
+            None
+        }
+    }
+
+    // Returns the location right before the grapheme or line break preceding the given location, if any
+    fn location_before(&self, at: Location) -> Option<Location> {
+        if at.grapheme_index > 0 {
+            Some(Location {
+                line_index: at.line_index,
+                grapheme_index: min(
+                    at.grapheme_index.saturating_sub(1),
+                    self.grapheme_count(at.line_index),
+                ),
+            })
+        } else if at.line_index > 0 && at.line_index < self.height() {
+            let line_index = at.line_index.saturating_sub(1);
+            Some(Location {
+                line_index,
+                grapheme_index: self.grapheme_count(line_index),
+            })
+        } else {
+            None
+        }
+    }
+
+    // Limits the range to the text of the document. The final line break can't be part of the range,
+    // since every line, the last one included, needs to end with a line break.
+    fn clamp_range(&self, range: Range<Location>) -> Range<Location> {
+        let last_line_index = self.height().saturating_sub(1);
+        let end_of_document = Location {
+            line_index: last_line_index,
+            grapheme_index: self.grapheme_count(last_line_index),
+        };
+        let end = min(range.end, end_of_document);
+
+        min(range.start, end)..end
+    }
+
+    // END SECTION
+
+    // SECTION: raw text manipulation, bypassing the history
+
+    fn to_text_position(&self, location: Location) -> TextPosition {
+        TextPosition {
+            line_index: location.line_index,
+            byte_index: self.line(location.line_index).map_or(0, |line| {
+                line.grapheme_idx_to_byte_idx(location.grapheme_index)
+            }),
+        }
+    }
+
+    fn to_location(&self, position: TextPosition) -> Location {
+        Location {
+            line_index: position.line_index,
+            grapheme_index: self.line(position.line_index).map_or(0, |line| {
+                line.byte_idx_to_grapheme_idx(position.byte_index)
+                    .unwrap_or_else(|| line.grapheme_count())
+            }),
+        }
+    }
+
+    fn apply_all(&mut self, operations: &[Operation]) {
+        for operation in operations {
+            match operation {
+                Operation::Insert { at, text } => {
+                    self.apply_insert(*at, text);
+                }
+                Operation::Delete { at, text } => {
+                    let end = Self::end_of_text(*at, text);
+                    self.apply_delete(*at, end);
+                }
+            }
+        }
+    }
+
+    fn end_of_text(at: TextPosition, text: &str) -> TextPosition {
+        match text.rfind('\n') {
+            Some(idx) => TextPosition {
+                line_index: at.line_index.saturating_add(text.matches('\n').count()),
+                byte_index: text.len().saturating_sub(idx.saturating_add(1)),
+            },
+            None => TextPosition {
+                line_index: at.line_index,
+                byte_index: at.byte_index.saturating_add(text.len()),
+            },
+        }
+    }
+
+    fn to_char_idx(&self, position: TextPosition) -> usize {
+        let line_index = min(position.line_index, self.text.len_lines());
+        let byte_idx = self
+            .text
+            .line_to_byte(line_index)
+            .saturating_add(position.byte_index);
+
+        self.text.byte_to_char(min(byte_idx, self.text.len_bytes()))
+    }
+
+    // Inserts the text, which may span multiple lines, and returns the position right after it
+    fn apply_insert(&mut self, at: TextPosition, text: &str) -> TextPosition {
+        let start = self.to_location(at);
+        self.text.insert(self.to_char_idx(at), text);
+        self.forget_lines(
+            at.line_index,
+            1,
+            text.matches('\n').count().saturating_add(1),
+        );
+
+        let end = Self::end_of_text(at, text);
+        self.changes.push(TextChange {
+            start,
+            removed_end: start,
+            inserted_end: self.to_location(end),
+        });
+        end
+    }
+
+    // Removes the text between the two positions and returns it, line breaks included
+    fn apply_delete(&mut self, start: TextPosition, end: TextPosition) -> String {
+        let first = start.line_index;
+        let change = TextChange {
+            start: self.to_location(start),
+            removed_end: self.to_location(end),
+            inserted_end: self.to_location(start),
+        };
+        let end = self.to_char_idx(end);
+        let start = min(self.to_char_idx(start), end);
+        let removed = self.text.slice(start..end).to_string();
+
+        self.text.remove(start..end);
+        self.forget_lines(first, removed.matches('\n').count().saturating_add(1), 1);
+        self.changes.push(change);
+
+        removed
+    }
+
+    // END SECTION
+}
@@ -0,0 +1,49 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, Metadata},
+    hash::{Hash, Hasher},
+    io::Error,
+    path::Path,
+    time::SystemTime,
+};
+
+// What the file looked like on disk when it was last loaded or saved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl DiskState {
+    pub fn from(contents: &[u8], metadata: &Metadata) -> Self {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let metadata = fs::metadata(path)?;
+        let contents = fs::read(path)?;
+        Ok(Self::from(&contents, &metadata))
+    }
+
+    // Tells whether the file was written to since. A file that was only touched, or rewritten with
+    // the same contents, doesn't count. If the file can't be read anymore, there's nothing to clobber either.
+    pub fn has_changed(&self, path: &Path) -> bool {
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+
+        if metadata.len() == self.len && metadata.modified().ok() == self.modified {
+            return false;
+        }
+
+        Self::read(path).is_ok_and(|current| current.len != self.len || current.hash != self.hash)
+    }
+}
@@ -1,53 +1,134 @@
-use super::super::super::FileType;
-use std::{
-    fmt::{self, Display},
-    path::{Path, PathBuf},
-};
-#[derive(Default, Debug)]
-pub struct FileInfo {
-    file_type: FileType,
-    path: Option<PathBuf>,
-}
-
-impl FileInfo {
-    pub fn from(file_name: &str) -> Self {
-        let path = PathBuf::from(file_name);
-        let file_type = if path
-            .extension()
-            .map_or(false, |ext| ext.eq_ignore_ascii_case("rs"))
-        {
-            FileType::Rust
-        } else {
-            FileType::Text
-        };
-
-        Self {
-            path: Some(path),
-            file_type,
-        }
-    }
-
-    pub fn get_path(&self) -> Option<&Path> {
-        self.path.as_deref()
-    }
-
-    pub const fn has_path(&self) -> bool {
-        self.path.is_some()
-    }
-
-    pub const fn get_file_type(&self) -> FileType {
-        self.file_type
-    }
-}
-
-impl Display for FileInfo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = self
-            .get_path()
-            .and_then(|p| p.file_name())
-            .and_then(|name| name.to_str())
-            .unwrap_or("Untitled");
-
-        write!(f, "{}", name)
-    }
-}
+use super::super::super::{Encoding, FileType, LineEnding};
+use super::DiskState;
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+#[derive(Default, Debug)]
+pub struct FileInfo {
+    file_type: FileType,
+    path: Option<PathBuf>,
+    line_ending: LineEnding,
+    mixed_line_endings: bool, // the file uses both LF and CRLF, saving converts every line to `line_ending`
+    missing_final_newline: bool, // the last line of the file doesn't end with a line break
+    has_bom: bool,            // the file starts with a byte order mark
+    encoding: Encoding,
+    is_read_only: bool, // the file doesn't look like text, saving it could corrupt it
+    disk_state: Option<DiskState>, // the file as we last loaded or saved it, to notice when someone else changes it
+}
+
+impl FileInfo {
+    pub fn from(file_name: &str) -> Self {
+        let path = PathBuf::from(file_name);
+        let file_type = if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"))
+        {
+            FileType::Rust
+        } else {
+            FileType::Text
+        };
+
+        Self {
+            path: Some(path),
+            file_type,
+            ..Self::default()
+        }
+    }
+
+    // Points to another file, keeping the format the document is written in
+    pub fn renamed(&self, file_name: &str) -> Self {
+        Self {
+            line_ending: self.line_ending,
+            missing_final_newline: self.missing_final_newline,
+            has_bom: self.has_bom,
+            encoding: self.encoding,
+            is_read_only: self.is_read_only,
+            ..Self::from(file_name)
+        }
+    }
+
+    // Remembers how the file contents are laid out, so they can be written back the same way
+    pub fn detect_format(&mut self, contents: &str) {
+        self.has_bom = contents.starts_with('\u{feff}');
+        self.line_ending = LineEnding::detect(contents);
+        self.mixed_line_endings = LineEnding::is_mixed(contents);
+        self.missing_final_newline = !contents.is_empty() && !contents.ends_with('\n');
+    }
+
+    pub const fn get_line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    pub const fn has_mixed_line_endings(&self) -> bool {
+        self.mixed_line_endings
+    }
+
+    // Once saved, every line ends the same way
+    pub fn clear_mixed_line_endings(&mut self) {
+        self.mixed_line_endings = false;
+    }
+
+    pub const fn has_final_newline(&self) -> bool {
+        !self.missing_final_newline
+    }
+
+    pub const fn has_bom(&self) -> bool {
+        self.has_bom
+    }
+
+    pub const fn get_encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub const fn is_read_only(&self) -> bool {
+        self.is_read_only
+    }
+
+    pub fn set_read_only(&mut self, is_read_only: bool) {
+        self.is_read_only = is_read_only;
+    }
+
+    pub fn set_disk_state(&mut self, disk_state: Option<DiskState>) {
+        self.disk_state = disk_state;
+    }
+
+    pub fn has_changed_on_disk(&self) -> bool {
+        match (&self.path, &self.disk_state) {
+            (Some(path), Some(disk_state)) => disk_state.has_changed(path),
+            _ => false,
+        }
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub const fn has_path(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub const fn get_file_type(&self) -> FileType {
+        self.file_type
+    }
+}
+
+impl Display for FileInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .get_path()
+            .and_then(|p| p.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled");
+
+        write!(f, "{}", name)
+    }
+}
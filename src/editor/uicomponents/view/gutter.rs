@@ -0,0 +1,57 @@
+use super::super::super::{AnnotatedString, AnnotationType, LineNumbers};
+use crate::prelude::*;
+
+// The columns left of the text. Only line numbers for now, further markers such as diagnostics
+// or diff signs get their own columns next to them.
+#[derive(Default, Clone, Copy)]
+pub struct Gutter {
+    pub line_numbers: LineNumbers,
+}
+
+impl Gutter {
+    // How many columns the gutter takes for a document of `line_count` lines
+    pub fn width(self, line_count: usize) -> ColIdx {
+        self.line_numbers_width(line_count)
+    }
+
+    // The line numbers are as wide as the largest one, followed by a blank column
+    fn line_numbers_width(self, line_count: usize) -> ColIdx {
+        if self.line_numbers == LineNumbers::Off {
+            return 0;
+        }
+
+        line_count.max(1).to_string().len().saturating_add(1)
+    }
+
+    // What to show next to a screen row. `line_idx` is None for rows past the end of the document,
+    // `is_first_row` is false for the rows a soft wrapped line continues on.
+    pub fn render(
+        self,
+        line_idx: Option<LineIdx>,
+        is_first_row: bool,
+        caret_line_idx: LineIdx,
+        line_count: usize,
+    ) -> AnnotatedString {
+        let number_width = self.line_numbers_width(line_count).saturating_sub(1);
+        if number_width == 0 {
+            return AnnotatedString::default();
+        }
+
+        let number = line_idx
+            .filter(|_| is_first_row)
+            .and_then(|line_idx| self.line_numbers.number(line_idx, caret_line_idx));
+
+        let Some(number) = number else {
+            return AnnotatedString::from(&" ".repeat(number_width.saturating_add(1)));
+        };
+
+        let mut result = AnnotatedString::from(&format!("{number:>number_width$} "));
+        let annotation_type = if line_idx == Some(caret_line_idx) {
+            AnnotationType::CurrentLineNumber
+        } else {
+            AnnotationType::LineNumber
+        };
+        result.add_annotation(annotation_type, 0, number_width);
+        result
+    }
+}
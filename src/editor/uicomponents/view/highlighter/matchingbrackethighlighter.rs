@@ -0,0 +1,46 @@
+use super::{syntaxhighlighter::SyntaxHighlighter, Annotation, AnnotationType, Line};
+use crate::prelude::*;
+use std::ops::Range;
+
+// Marks the partner of the bracket the caret is on
+pub struct MatchingBracketHighlighter {
+    location: Location,
+    highlights: Vec<Annotation>,
+}
+
+impl MatchingBracketHighlighter {
+    pub const fn new(location: Location) -> Self {
+        Self {
+            location,
+            highlights: Vec::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for MatchingBracketHighlighter {
+    fn highlight(
+        &mut self,
+        line_idx: LineIdx,
+        line: &Line,
+        _search_results: &Option<Vec<Range<GraphemeIdx>>>,
+    ) {
+        if line_idx != self.location.line_index {
+            return;
+        }
+
+        let start = line.grapheme_idx_to_byte_idx(self.location.grapheme_index);
+        let end = line.grapheme_idx_to_byte_idx(self.location.grapheme_index.saturating_add(1));
+
+        if start < end {
+            self.highlights = vec![Annotation {
+                annotation_type: AnnotationType::MatchingBracket,
+                start,
+                end,
+            }];
+        }
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        (idx == self.location.line_index).then_some(&self.highlights)
+    }
+}
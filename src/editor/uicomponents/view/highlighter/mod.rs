@@ -1,71 +1,142 @@
-use super::super::super::{Annotation, AnnotationType, FileType, Line};
-use crate::prelude::*;
-use rustsyntaxhighlighter::RustSyntaxHighlighter;
-use searchresulthighlighter::SearchResultHighlighter;
-use syntaxhighlighter::SyntaxHighlighter;
-
-mod rustsyntaxhighlighter;
-mod searchresulthighlighter;
-mod syntaxhighlighter;
-
-fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
-    match file_type {
-        FileType::Rust => Some(Box::new(RustSyntaxHighlighter::default())),
-        _ => None,
-    }
-}
-
-#[derive(Default)]
-pub struct Highlighter<'a> {
-    syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
-    search_result_highlighter: Option<SearchResultHighlighter<'a>>,
-}
-
-impl<'a> Highlighter<'a> {
-    pub fn new(
-        file_type: FileType,
-        matched_word: Option<&'a str>,
-        selected_match: Option<Location>,
-    ) -> Self {
-        let search_result_highlighter = matched_word
-            .map(|matched_word| SearchResultHighlighter::new(matched_word, selected_match));
-
-        Self {
-            syntax_highlighter: create_syntax_highlighter(file_type),
-            search_result_highlighter,
-        }
-    }
-
-    pub fn get_annotations(&self, line_idx: LineIdx) -> Vec<Annotation> {
-        let mut result = Vec::new();
-
-        if let Some(syntax_highlighter) = &self.syntax_highlighter {
-            if let Some(annotations) = syntax_highlighter.get_annotations(line_idx) {
-                result.extend(annotations.iter().copied());
-            }
-        }
-
-        if let Some(search_result_highlighter) = &self.search_result_highlighter {
-            if let Some(annotations) = search_result_highlighter.get_annotations(line_idx) {
-                result.extend(annotations.iter().copied());
-            }
-        }
-
-        result
-    }
-
-    pub fn highlight(
-        &mut self,
-        line_idx: LineIdx,
-        line: &Line,
-        search_results: &Option<Vec<GraphemeIdx>>,
-    ) {
-        if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
-            syntax_highlighter.highlight(line_idx, line, search_results);
-        }
-
-        if let Some(search_result_highlighter) = &mut self.search_result_highlighter {
-            search_result_highlighter.highlight(line_idx, line, search_results);
-        }
-    }
-}
+use super::super::super::{Annotation, AnnotationType, FileType, Line};
+use crate::prelude::*;
+use matchingbrackethighlighter::MatchingBracketHighlighter;
+use rustsyntaxhighlighter::RustSyntaxHighlighter;
+use searchresulthighlighter::SearchResultHighlighter;
+use selectionhighlighter::SelectionHighlighter;
+use std::ops::Range;
+use syntaxhighlighter::SyntaxHighlighter;
+pub use syntaxhighlighter::SyntaxState;
+
+mod matchingbrackethighlighter;
+mod rustsyntaxhighlighter;
+mod searchresulthighlighter;
+mod selectionhighlighter;
+mod syntaxhighlighter;
+
+fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
+    match file_type {
+        FileType::Rust => Some(Box::new(RustSyntaxHighlighter::default())),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+pub struct Highlighter {
+    syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
+    search_result_highlighter: Option<SearchResultHighlighter>,
+    selection_highlighter: Option<SelectionHighlighter>,
+    matching_bracket_highlighter: Option<MatchingBracketHighlighter>,
+}
+
+impl Highlighter {
+    // Search results are only highlighted while searching, `selected_match` is the one the caret is at.
+    // `matching_bracket` is the partner of the bracket the caret is on.
+    pub fn new(
+        file_type: FileType,
+        is_searching: bool,
+        selected_match: Option<Range<Location>>,
+        selection: Option<Range<Location>>,
+        matching_bracket: Option<Location>,
+    ) -> Self {
+        let search_result_highlighter =
+            is_searching.then(|| SearchResultHighlighter::new(selected_match));
+
+        Self {
+            syntax_highlighter: create_syntax_highlighter(file_type),
+            search_result_highlighter,
+            selection_highlighter: selection.map(SelectionHighlighter::new),
+            matching_bracket_highlighter: matching_bracket.map(MatchingBracketHighlighter::new),
+        }
+    }
+
+    pub fn has_syntax(&self) -> bool {
+        self.syntax_highlighter.is_some()
+    }
+
+    // The state the line after the last highlighted one starts in
+    pub fn syntax_state(&self) -> SyntaxState {
+        self.syntax_highlighter
+            .as_ref()
+            .map_or_else(SyntaxState::default, |syntax_highlighter| {
+                syntax_highlighter.state()
+            })
+    }
+
+    // Syntax highlighting carries state from one line to the next. To start at a line other than the first,
+    // the highlighter needs the state that line starts in.
+    pub fn set_syntax_state(&mut self, state: SyntaxState) {
+        if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
+            syntax_highlighter.set_state(state);
+        }
+    }
+
+    pub fn get_annotations(&self, line_idx: LineIdx) -> Vec<Annotation> {
+        let mut result = Vec::new();
+
+        if let Some(syntax_highlighter) = &self.syntax_highlighter {
+            if let Some(annotations) = syntax_highlighter.get_annotations(line_idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+
+        if let Some(search_result_highlighter) = &self.search_result_highlighter {
+            if let Some(annotations) = search_result_highlighter.get_annotations(line_idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+
+        if let Some(selection_highlighter) = &self.selection_highlighter {
+            if let Some(annotations) = selection_highlighter.get_annotations(line_idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+
+        if let Some(matching_bracket_highlighter) = &self.matching_bracket_highlighter {
+            if let Some(annotations) = matching_bracket_highlighter.get_annotations(line_idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+
+        result
+    }
+
+    // Whether the byte is code, as opposed to part of a comment, string or character literal.
+    // Without syntax highlighting for the file type, everything counts as code.
+    pub fn is_code(&self, line_idx: LineIdx, byte_idx: ByteIdx) -> bool {
+        self.syntax_highlighter
+            .as_ref()
+            .and_then(|syntax_highlighter| syntax_highlighter.get_annotations(line_idx))
+            .is_none_or(|annotations| {
+                !annotations.iter().any(|annotation| {
+                    matches!(
+                        annotation.annotation_type,
+                        AnnotationType::Comment | AnnotationType::String | AnnotationType::Char
+                    ) && (annotation.start..annotation.end).contains(&byte_idx)
+                })
+            })
+    }
+
+    pub fn highlight(
+        &mut self,
+        line_idx: LineIdx,
+        line: &Line,
+        search_results: &Option<Vec<Range<GraphemeIdx>>>,
+    ) {
+        if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
+            syntax_highlighter.highlight(line_idx, line, search_results);
+        }
+
+        if let Some(search_result_highlighter) = &mut self.search_result_highlighter {
+            search_result_highlighter.highlight(line_idx, line, search_results);
+        }
+
+        if let Some(selection_highlighter) = &mut self.selection_highlighter {
+            selection_highlighter.highlight(line_idx, line, search_results);
+        }
+
+        if let Some(matching_bracket_highlighter) = &mut self.matching_bracket_highlighter {
+            matching_bracket_highlighter.highlight(line_idx, line, search_results);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::{syntaxhighlighter::SyntaxHighlighter, Annotation, AnnotationType, Line};
+use crate::prelude::*;
+use std::{collections::HashMap, ops::Range};
+
+pub struct SelectionHighlighter {
+    selection: Range<Location>,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl SelectionHighlighter {
+    pub fn new(selection: Range<Location>) -> Self {
+        Self {
+            selection,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for SelectionHighlighter {
+    fn highlight(
+        &mut self,
+        line_idx: LineIdx,
+        line: &Line,
+        _search_results: &Option<Vec<Range<GraphemeIdx>>>,
+    ) {
+        let Range { start, end } = self.selection;
+
+        if line_idx < start.line_index || line_idx > end.line_index {
+            return;
+        }
+
+        let start_byte = if line_idx == start.line_index {
+            line.grapheme_idx_to_byte_idx(start.grapheme_index)
+        } else {
+            0
+        };
+
+        let end_byte = if line_idx == end.line_index {
+            line.grapheme_idx_to_byte_idx(end.grapheme_index)
+        } else {
+            line.len()
+        };
+
+        if start_byte < end_byte {
+            self.highlights.insert(
+                line_idx,
+                vec![Annotation {
+                    annotation_type: AnnotationType::Selection,
+                    start: start_byte,
+                    end: end_byte,
+                }],
+            );
+        }
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
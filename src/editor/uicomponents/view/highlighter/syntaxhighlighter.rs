@@ -1,8 +1,27 @@
-use super::Annotation;
-use super::Line;
-use crate::prelude::*;
-
-pub trait SyntaxHighlighter {
-    fn highlight(&mut self, idx: LineIdx, line: &Line, search_results: &Option<Vec<GraphemeIdx>>);
-    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>>;
-}
+use super::Annotation;
+use super::Line;
+use crate::prelude::*;
+use std::ops::Range;
+
+// What's still open where a line starts, e.g. a multi-line comment or string.
+// Given that, highlighting can start at any line rather than at the top of the document.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct SyntaxState {
+    pub ml_comment_balance: usize,
+    pub in_ml_string: bool,
+}
+
+pub trait SyntaxHighlighter {
+    fn highlight(
+        &mut self,
+        idx: LineIdx,
+        line: &Line,
+        search_results: &Option<Vec<Range<GraphemeIdx>>>,
+    );
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>>;
+    // The state the next line starts in. Highlighters looking at each line on its own have none.
+    fn state(&self) -> SyntaxState {
+        SyntaxState::default()
+    }
+    fn set_state(&mut self, _state: SyntaxState) {}
+}
@@ -0,0 +1,361 @@
+use crate::prelude::*;
+
+// A position inside the buffer expressed in bytes rather than graphemes.
+// Operations are recorded this way so that undoing an edit which merged two graphemes
+// (e.g. a combining accent) restores the exact original text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextPosition {
+    pub line_index: LineIdx,
+    pub byte_index: ByteIdx,
+}
+
+#[derive(Clone, Debug)]
+pub enum Operation {
+    Insert { at: TextPosition, text: String },
+    Delete { at: TextPosition, text: String },
+}
+
+impl Operation {
+    fn inverted(&self) -> Self {
+        match self {
+            Self::Insert { at, text } => Self::Delete {
+                at: *at,
+                text: text.clone(),
+            },
+            Self::Delete { at, text } => Self::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+        }
+    }
+}
+
+// Describes what kind of edit produced an operation.
+// Consecutive edits of the same kind are grouped into a single undo step, except for `Other`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Typing,
+    Deletion,
+    BackwardDeletion,
+    Other,
+}
+
+struct Transaction {
+    id: usize,
+    kind: ChangeKind,
+    operations: Vec<Operation>,
+    caret_before: Location,
+    caret_after: Location,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    last_id: usize, // every change to the undo stack gets a fresh id, 0 being the initial state
+    saved_id: usize, // id of the state that was last loaded from or written to disk
+    sealed: bool,   // when set, the next change can't be grouped with the previous one
+    group_depth: usize, // while above 0, every change is added to the same undo step
+}
+
+impl History {
+    pub fn record(
+        &mut self,
+        operation: Operation,
+        kind: ChangeKind,
+        caret_before: Location,
+        caret_after: Location,
+    ) {
+        self.redo_stack.clear();
+        self.last_id = self.last_id.saturating_add(1);
+
+        if !self.sealed {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if self.group_depth > 0
+                    || (kind != ChangeKind::Other
+                        && last.kind == kind
+                        && last.caret_after == caret_before)
+                {
+                    last.operations.push(operation);
+                    last.caret_after = caret_after;
+                    last.id = self.last_id;
+                    return;
+                }
+            }
+        }
+
+        self.sealed = false;
+        self.undo_stack.push(Transaction {
+            id: self.last_id,
+            kind,
+            operations: vec![operation],
+            caret_before,
+            caret_after,
+        });
+    }
+
+    // Every change recorded until `end_group` is called will be undone in a single step.
+    // Groups can be nested, the changes are grouped until the outermost one ends.
+    pub fn begin_group(&mut self) {
+        if self.group_depth == 0 {
+            self.sealed = true;
+        }
+        self.group_depth = self.group_depth.saturating_add(1);
+    }
+
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth == 0 {
+            self.sealed = true;
+        }
+    }
+
+    // Returns the operations which revert the last change, in the order they need to be applied,
+    // along with the caret location from before that change.
+    pub fn undo(&mut self) -> Option<(Vec<Operation>, Location)> {
+        let transaction = self.undo_stack.pop()?;
+        let operations = transaction
+            .operations
+            .iter()
+            .rev()
+            .map(Operation::inverted)
+            .collect();
+        let caret = transaction.caret_before;
+
+        self.redo_stack.push(transaction);
+        self.sealed = true;
+
+        Some((operations, caret))
+    }
+
+    // Returns the operations which reapply the last undone change,
+    // along with the caret location from after that change.
+    pub fn redo(&mut self) -> Option<(Vec<Operation>, Location)> {
+        let transaction = self.redo_stack.pop()?;
+        let operations = transaction.operations.clone();
+        let caret = transaction.caret_after;
+
+        self.undo_stack.push(transaction);
+        self.sealed = true;
+
+        Some((operations, caret))
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_id = self.current_id();
+        self.sealed = true;
+    }
+
+    // Identifies the current state of the document: every change, undo and redo leads to another revision
+    pub fn revision(&self) -> usize {
+        self.current_id()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.current_id() != self.saved_id
+    }
+
+    fn current_id(&self) -> usize {
+        self.undo_stack
+            .last()
+            .map_or(0, |transaction| transaction.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(grapheme_index: GraphemeIdx) -> Location {
+        Location {
+            line_index: 0,
+            grapheme_index,
+        }
+    }
+
+    fn insert(byte_index: ByteIdx, text: &str) -> Operation {
+        Operation::Insert {
+            at: TextPosition {
+                line_index: 0,
+                byte_index,
+            },
+            text: text.to_string(),
+        }
+    }
+
+    // Types the text one character at a time, starting at the given column
+    fn type_text(history: &mut History, from: usize, text: &str) {
+        for (offset, character) in text.chars().enumerate() {
+            let at = from.saturating_add(offset);
+            history.record(
+                insert(at, &character.to_string()),
+                ChangeKind::Typing,
+                location(at),
+                location(at.saturating_add(1)),
+            );
+        }
+    }
+
+    fn texts(operations: &[Operation]) -> Vec<&str> {
+        operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Insert { text, .. } | Operation::Delete { text, .. } => text.as_str(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn consecutive_typing_is_undone_in_one_step() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "abc");
+
+        let (operations, caret) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["c", "b", "a"]);
+        assert!(operations
+            .iter()
+            .all(|operation| matches!(operation, Operation::Delete { .. })));
+        assert_eq!(caret, location(0));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn typing_elsewhere_starts_a_new_step() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+        type_text(&mut history, 5, "cd");
+
+        let (operations, caret) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["d", "c"]);
+        assert_eq!(caret, location(5));
+        let (operations, _) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["b", "a"]);
+    }
+
+    #[test]
+    fn other_changes_are_never_grouped() {
+        let mut history = History::default();
+        history.record(insert(0, "a"), ChangeKind::Other, location(0), location(1));
+        history.record(insert(1, "b"), ChangeKind::Other, location(1), location(2));
+
+        let (operations, _) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["b"]);
+    }
+
+    #[test]
+    fn a_group_is_undone_in_one_step() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+
+        history.begin_group();
+        history.record(insert(2, "x"), ChangeKind::Other, location(2), location(3));
+        history.begin_group();
+        history.record(
+            insert(3, "y"),
+            ChangeKind::Deletion,
+            location(3),
+            location(4),
+        );
+        history.end_group();
+        history.record(insert(4, "z"), ChangeKind::Other, location(4), location(5));
+        history.end_group();
+        type_text(&mut history, 5, "c");
+
+        let (operations, _) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["c"]);
+        let (operations, caret) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["z", "y", "x"]);
+        assert_eq!(caret, location(2));
+        let (operations, _) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["b", "a"]);
+    }
+
+    #[test]
+    fn redo_reapplies_in_the_original_order() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+        history.record(insert(2, "c"), ChangeKind::Other, location(2), location(3));
+
+        history.undo().unwrap();
+        history.undo().unwrap();
+
+        let (operations, caret) = history.redo().unwrap();
+        assert_eq!(texts(&operations), ["a", "b"]);
+        assert!(operations
+            .iter()
+            .all(|operation| matches!(operation, Operation::Insert { .. })));
+        assert_eq!(caret, location(2));
+        let (operations, caret) = history.redo().unwrap();
+        assert_eq!(texts(&operations), ["c"]);
+        assert_eq!(caret, location(3));
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn a_new_edit_clears_the_redo_stack() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+        history.undo().unwrap();
+
+        type_text(&mut history, 0, "c");
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn typing_after_an_undo_starts_a_new_step() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+        type_text(&mut history, 2, "c");
+        history.undo().unwrap();
+        history.redo().unwrap();
+        type_text(&mut history, 3, "d");
+
+        let (operations, _) = history.undo().unwrap();
+        assert_eq!(texts(&operations), ["d"]);
+    }
+
+    #[test]
+    fn undoing_back_to_the_saved_state_makes_it_clean() {
+        let mut history = History::default();
+        assert!(!history.is_dirty());
+
+        type_text(&mut history, 0, "ab");
+        assert!(history.is_dirty());
+        history.mark_saved();
+        assert!(!history.is_dirty());
+
+        type_text(&mut history, 2, "c");
+        assert!(history.is_dirty());
+        history.undo().unwrap();
+        assert!(!history.is_dirty());
+        history.redo().unwrap();
+        assert!(history.is_dirty());
+    }
+
+    #[test]
+    fn undoing_past_the_saved_state_makes_it_dirty() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "ab");
+        history.mark_saved();
+
+        history.undo().unwrap();
+        assert!(history.is_dirty());
+        history.redo().unwrap();
+        assert!(!history.is_dirty());
+    }
+
+    #[test]
+    fn the_saved_state_is_gone_once_an_edit_replaces_it() {
+        let mut history = History::default();
+        type_text(&mut history, 0, "a");
+        history.mark_saved();
+        history.undo().unwrap();
+
+        type_text(&mut history, 0, "b");
+        assert!(history.is_dirty());
+        history.undo().unwrap();
+        assert!(history.is_dirty());
+        assert!(history.redo().is_some());
+        assert!(history.is_dirty());
+    }
+}
@@ -1,430 +1,1364 @@
-use super::super::{
-    command::{Edit, Move},
-    DocumentStatus, Line, Terminal,
-};
-use super::UIComponent;
-use crate::editor::RowIdx;
-use crate::prelude::*;
-use buffer::Buffer;
-use fileinfo::FileInfo;
-use highlighter::Highlighter;
-use searchinfo::SearchInfo;
-use std::{cmp::min, io::Error};
-
-mod buffer;
-mod fileinfo;
-mod highlighter;
-mod searchinfo;
-
-#[derive(Default)]
-pub struct View {
-    buffer: Buffer,
-    needs_redraw: bool,
-    prev_text_location: Location,
-    text_location: Location,
-    scroll_offset: Position,
-    search_info: Option<SearchInfo>,
-    size: Size,
-}
-
-impl View {
-    pub fn get_status(&self) -> DocumentStatus {
-        let file_info = self.buffer.get_file_info();
-
-        DocumentStatus {
-            current_grapheme_index: self.text_location.grapheme_index,
-            current_line_index: self.text_location.line_index,
-            file_name: format!("{file_info}"),
-            file_type: file_info.get_file_type(),
-            is_modified: self.buffer.is_dirty(),
-            total_lines: self.buffer.height(),
-        }
-    }
-
-    pub const fn is_file_loaded(&self) -> bool {
-        self.buffer.is_file_loaded()
-    }
-
-    // SECTION: search
-
-    pub fn enter_search(&mut self) {
-        self.search_info = Some(SearchInfo {
-            current_idx: None,
-            prev_location: self.text_location,
-            prev_scroll_offset: self.scroll_offset,
-            query: None,
-            result: None,
-        });
-    }
-
-    pub fn exit_search(&mut self) {
-        self.search_info = None;
-        self.set_needs_redraw(true);
-    }
-
-    pub fn dismiss_search(&mut self) {
-        if let Some(search_info) = &self.search_info {
-            self.text_location = search_info.prev_location;
-        }
-
-        self.exit_search();
-    }
-
-    pub fn search(&mut self, query: &str) {
-        if query.is_empty() {
-            self.search_info = None;
-            self.set_needs_redraw(true);
-            return;
-        }
-
-        if let Some(location) = self.buffer.search(query) {
-            self.search_info = Some(SearchInfo {
-                current_idx: Some(0),
-                prev_location: self.text_location,
-                prev_scroll_offset: self.scroll_offset,
-                query: Some(Line::from(query)),
-                result: Some(location),
-            });
-        } else {
-            self.search_info = None;
-        }
-
-        if let Some(search_info) = &self.search_info {
-            if let Some(location) = &search_info.result {
-                self.text_location = location[search_info.current_idx.unwrap()];
-            }
-        }
-        self.set_needs_redraw(true);
-        self.scroll_text_location_into_view();
-    }
-
-    pub fn next_search_result(&mut self) {
-        if let Some(search_info) = &mut self.search_info {
-            if let Some(location) = &search_info.result {
-                let len: usize = location.len();
-                search_info.current_idx = Some((search_info.current_idx.unwrap() + 1) % len);
-                self.text_location = location[search_info.current_idx.unwrap()];
-                self.set_needs_redraw(true);
-            }
-        }
-    }
-
-    pub fn prev_search_result(&mut self) {
-        if let Some(search_info) = &mut self.search_info {
-            if let Some(location) = &search_info.result {
-                let len: usize = location.len();
-                search_info.current_idx = Some((search_info.current_idx.unwrap() + len - 1) % len);
-                self.text_location = location[search_info.current_idx.unwrap()];
-                self.set_needs_redraw(true);
-            }
-        }
-    }
-
-    // END SECTION
-
-    pub fn handle_edit_command(&mut self, command: Edit) {
-        match command {
-            Edit::Insert(character) => self.insert_char(character),
-            Edit::DeleteBackward => self.delete_char_backward(),
-            Edit::Delete => self.delete_char(),
-            Edit::InsertNewline => self.insert_new_line(),
-        }
-    }
-
-    pub fn handle_move_command(&mut self, command: Move) {
-        let Size { height, .. } = self.size;
-        match command {
-            Move::Up => self.move_up(1),
-            Move::Down => self.move_down(1),
-            Move::Left => self.move_left(),
-            Move::Right => self.move_right(),
-            Move::PageUp => self.move_up(height.saturating_sub(1)),
-            Move::PageDown => self.move_down(height.saturating_sub(1)),
-            Move::StartOfLine => self.move_to_start_of_line(),
-            Move::EndOfLine => self.move_to_end_of_line(),
-        }
-
-        self.scroll_text_location_into_view();
-    }
-
-    fn delete_char_backward(&mut self) {
-        if self.text_location.line_index != 0 || self.text_location.grapheme_index != 0 {
-            self.handle_move_command(Move::Left);
-            self.delete_char();
-        }
-    }
-
-    fn delete_char(&mut self) {
-        self.buffer.delete_char(self.text_location);
-        self.set_needs_redraw(true);
-    }
-
-    fn insert_char(&mut self, character: char) {
-        let old_len = self.buffer.grapheme_count(self.text_location.line_index);
-
-        self.buffer.insert_char(character, self.text_location);
-
-        let new_len = self.buffer.grapheme_count(self.text_location.line_index);
-
-        let grapheme_delta = new_len.saturating_sub(old_len);
-
-        if grapheme_delta > 0 {
-            self.handle_move_command(Move::Right);
-        }
-
-        self.set_needs_redraw(true);
-    }
-
-    fn insert_new_line(&mut self) {
-        self.buffer.insert_new_line(self.text_location);
-        self.handle_move_command(Move::Right);
-        self.set_needs_redraw(true);
-    }
-
-    fn render_line(at: RowIdx, lines: &str) -> Result<(), Error> {
-        Terminal::print_row(at, lines)
-    }
-
-    fn build_welcome_message(width: usize) -> String {
-        if width == 0 {
-            return String::new();
-        }
-
-        let welcome_message: String = format!("{NAME} - version {VERSION}");
-        let len: usize = welcome_message.len();
-
-        let remaining_width: usize = width.saturating_sub(1);
-
-        if remaining_width <= len {
-            return "~".to_string();
-        }
-
-        format!("{:<1}{:^remaining_width$}", "~", welcome_message)
-    }
-
-    fn scroll_vertically(&mut self, to: RowIdx) {
-        let Size { height, .. } = self.size;
-        let offset_changed = if to < self.scroll_offset.row {
-            self.scroll_offset.row = to;
-            true
-        } else if to >= self.scroll_offset.row.saturating_add(height) {
-            self.scroll_offset.row = to.saturating_sub(height).saturating_add(1);
-            true
-        } else {
-            false
-        };
-
-        if offset_changed {
-            self.set_needs_redraw(true);
-        }
-
-        self.needs_redraw = offset_changed || self.needs_redraw;
-    }
-
-    fn scroll_horizontally(&mut self, to: ColIdx) {
-        let Size { width, .. } = self.size;
-        let offset_changed = if to < self.scroll_offset.col {
-            self.scroll_offset.col = to;
-            true
-        } else if to >= self.scroll_offset.col.saturating_add(width) {
-            self.scroll_offset.col = to.saturating_sub(width).saturating_add(1);
-            true
-        } else {
-            false
-        };
-
-        if offset_changed {
-            self.set_needs_redraw(true);
-        }
-        self.needs_redraw = self.needs_redraw || offset_changed;
-    }
-
-    fn scroll_text_location_into_view(&mut self) {
-        let Position { row, col } = self.text_location_to_position();
-
-        self.scroll_vertically(row);
-        self.scroll_horizontally(col);
-    }
-
-    pub fn caret_position(&self) -> Position {
-        self.text_location_to_position()
-            .saturating_sub(self.scroll_offset)
-    }
-
-    fn text_location_to_position(&self) -> Position {
-        let row = self.text_location.line_index;
-        let col = self
-            .buffer
-            .width_until(row, self.text_location.grapheme_index);
-        Position { row, col }
-    }
-
-    fn move_up(&mut self, step: usize) {
-        self.text_location.line_index = self.text_location.line_index.saturating_sub(step);
-        self.text_location.grapheme_index = min(
-            self.buffer.grapheme_count(self.text_location.line_index),
-            self.prev_text_location.grapheme_index,
-        );
-        self.snap_to_valid_grapheme();
-    }
-
-    fn move_down(&mut self, step: usize) {
-        self.text_location.line_index = self.text_location.line_index.saturating_add(step);
-        self.text_location.grapheme_index = min(
-            self.buffer
-                .grapheme_count(min(self.text_location.line_index, self.buffer.len() - 1)),
-            self.prev_text_location.grapheme_index,
-        );
-        self.snap_to_valid_grapheme();
-        self.snap_to_valid_line();
-    }
-
-    #[allow(clippy::arithmetic_side_effects)]
-    fn move_right(&mut self) {
-        let grapheme_count = self.buffer.grapheme_count(self.text_location.line_index);
-
-        if self.text_location.grapheme_index < grapheme_count {
-            self.text_location.grapheme_index += 1;
-        } else {
-            self.move_to_start_of_line();
-            self.move_down(1);
-        }
-        self.prev_text_location = self.text_location;
-    }
-
-    #[allow(clippy::arithmetic_side_effects)]
-    fn move_left(&mut self) {
-        if self.text_location.grapheme_index > 0 {
-            self.text_location.grapheme_index -= 1;
-        } else if self.text_location.line_index > 0 {
-            self.move_up(1);
-            self.move_to_end_of_line();
-        }
-        self.prev_text_location = self.text_location;
-    }
-
-    fn move_to_start_of_line(&mut self) {
-        self.text_location.grapheme_index = 0;
-        self.prev_text_location.grapheme_index = 0;
-    }
-
-    fn move_to_end_of_line(&mut self) {
-        self.text_location.grapheme_index =
-            self.buffer.grapheme_count(self.text_location.line_index);
-        self.prev_text_location.grapheme_index =
-            self.buffer.grapheme_count(self.text_location.line_index);
-    }
-
-    // Ensures self.location.grapheme_index points to a valid grapheme index by snapping it to the left most grapheme if appropriate.
-    // Doesn't trigger scrolling.
-    fn snap_to_valid_grapheme(&mut self) {
-        self.text_location.grapheme_index = min(
-            self.text_location.grapheme_index,
-            self.buffer.grapheme_count(self.text_location.line_index),
-        );
-    }
-
-    // Ensures self.location.line_index points to a valid line index by snapping it to the bottom most line if appropriate.
-    // Doesn't trigger scrolling.
-    fn snap_to_valid_line(&mut self) {
-        self.text_location.line_index = min(self.text_location.line_index, self.buffer.height());
-    }
-
-    pub fn load_file(&mut self, file_name: &str) -> Result<(), Error> {
-        let buffer = Buffer::load_file(file_name)?;
-        self.buffer = buffer;
-        self.set_needs_redraw(true);
-        Ok(())
-    }
-
-    pub fn save_file(&mut self) -> Result<(), Error> {
-        self.buffer.save()?;
-        self.set_needs_redraw(true);
-        Ok(())
-    }
-
-    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)?;
-        self.set_needs_redraw(true);
-        Ok(())
-    }
-}
-
-impl UIComponent for View {
-    fn set_needs_redraw(&mut self, value: bool) {
-        self.needs_redraw = value;
-    }
-
-    fn needs_redraw(&self) -> bool {
-        self.needs_redraw
-    }
-
-    fn set_size(&mut self, size: Size) {
-        self.size = size;
-        self.scroll_text_location_into_view();
-    }
-
-    fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
-        let Size { width, height } = self.size;
-        let end_y = origin_row.saturating_add(height);
-
-        #[allow(clippy::integer_division)]
-        let top_third = height / 3;
-        let scroll_top = self.scroll_offset.row;
-
-        let query: Option<&str> = self
-            .search_info
-            .as_ref()
-            .and_then(|search_info| search_info.query.as_deref());
-
-        let selected_match = query.is_some().then_some(self.text_location);
-
-        let mut highlighter = Highlighter::new(
-            self.buffer.get_file_info().get_file_type(),
-            query,
-            selected_match,
-        );
-
-        for current_row in 0..end_y.saturating_add(scroll_top) {
-            let search_results = if let Some(search_info) = &self.search_info {
-                if let Some(locations) = &search_info.result {
-                    let res = locations
-                        .iter()
-                        .filter(|location| location.line_index == current_row)
-                        .map(|location| location.grapheme_index)
-                        .collect::<Vec<_>>();
-                    Some(res)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-            self.buffer
-                .highlight(current_row, &search_results, &mut highlighter);
-        }
-
-        for current_row in origin_row..end_y {
-            let line_idx = current_row
-                .saturating_sub(origin_row)
-                .saturating_add(scroll_top);
-
-            let left = self.scroll_offset.col;
-            let right = self.scroll_offset.col.saturating_add(width);
-
-            if let Some(annotated_string) =
-                self.buffer
-                    .get_highlighted_substring(line_idx, left..right, &highlighter)
-            {
-                Terminal::print_annotated_row(current_row, &annotated_string)?;
-            } else if current_row == top_third && self.buffer.is_empty() {
-                Self::render_line(current_row, &Self::build_welcome_message(width))?;
-            } else {
-                Self::render_line(current_row, "~")?;
-            }
-        }
-
-        Ok(())
-    }
-}
+use super::super::{
+    command::{Edit, Move, SelectionMode},
+    config::{Indent, Settings},
+    DocumentStatus, Line, LineEnding, LineNumbers, TabStops, Terminal,
+};
+use super::UIComponent;
+use crate::editor::RowIdx;
+use crate::prelude::*;
+use atomicfile::AtomicFile;
+use buffer::Buffer;
+use diskstate::DiskState;
+use fileinfo::FileInfo;
+use gutter::Gutter;
+use highlighter::{Highlighter, SyntaxState};
+use regex::Regex;
+use searchinfo::SearchInfo;
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    cmp::{max, min, Ordering},
+    io::Error,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use swapfile::SwapFile;
+
+mod atomicfile;
+mod buffer;
+mod diskstate;
+mod fileinfo;
+mod gutter;
+mod highlighter;
+mod history;
+mod searchinfo;
+mod swapfile;
+
+pub use swapfile::flush_snapshots;
+
+// A window onto a buffer, with its own caret and scroll offset.
+// Several views can share the same buffer, e.g. when the screen is split.
+#[derive(Default, Clone)]
+pub struct View {
+    buffer: Rc<RefCell<Buffer>>,
+    needs_redraw: bool,
+    prev_text_location: Location,
+    text_location: Location,
+    synced_changes: usize, // how many of the buffer's changes the caret and the scroll offset follow
+    matching_bracket: Option<((Location, usize), Option<Location>)>, // the partner found for the caret location and change count
+    selection_anchor: Option<Location>, // the end of the selection opposite to the caret, if any
+    scroll_offset: Position, // the row is the line at the top, which is scrolled by whole lines
+    scroll_line_row: usize,  // with soft wrap, the first row of that line on screen
+    soft_wrap: bool,
+    gutter: Gutter,
+    search_info: Option<SearchInfo>,
+    size: Size,
+    keep_backup: bool, // keep the previous version of the file as `<file>~` when saving
+    settings: Settings,
+}
+
+impl View {
+    fn buffer(&self) -> Ref<'_, Buffer> {
+        self.buffer.borrow()
+    }
+
+    fn buffer_mut(&self) -> RefMut<'_, Buffer> {
+        self.buffer.borrow_mut()
+    }
+
+    pub const fn get_size(&self) -> Size {
+        self.size
+    }
+
+    // The partner of the bracket at the caret. It's only looked for again once the caret moves or the text changes.
+    fn matching_bracket(&mut self) -> Option<Location> {
+        let key = (self.text_location, self.buffer().change_count());
+        match self.matching_bracket {
+            Some((cached_key, partner)) if cached_key == key => partner,
+            _ => {
+                let partner = self.buffer().find_matching_bracket(self.text_location);
+                self.matching_bracket = Some((key, partner));
+                partner
+            }
+        }
+    }
+
+    // The view made the latest changes itself, so it's where it needs to be already
+    pub fn mark_synced(&mut self) {
+        let change_count = self.buffer().change_count();
+        self.synced_changes = change_count;
+    }
+
+    // Every view onto the buffer is where it needs to be, so the changes so far are of no use anymore
+    pub fn forget_synced_changes(&mut self) {
+        let change_count = self.buffer().change_count();
+        self.buffer_mut().forget_changes(change_count);
+    }
+
+    pub fn shares_buffer_with(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.buffer, &other.buffer)
+    }
+
+    // Another view changed the shared buffer: the caret, the selection and the scroll offset follow
+    // the text they were at. After a reload, or once the buffer forgot the changes, there's no telling:
+    // they're only kept within the text.
+    pub fn sync_with_buffer(&mut self) {
+        let buffer = Rc::clone(&self.buffer);
+        let buffer = buffer.borrow();
+        if let Some(changes) = buffer.changes_since(self.synced_changes) {
+            let scroll_line = self.scroll_offset.row;
+            for change in changes {
+                self.text_location = change.shift(self.text_location);
+                self.selection_anchor = self.selection_anchor.map(|anchor| change.shift(anchor));
+                self.scroll_offset.row = change
+                    .shift(Location {
+                        line_index: self.scroll_offset.row,
+                        grapheme_index: 0,
+                    })
+                    .line_index;
+            }
+            if self.scroll_offset.row != scroll_line {
+                self.scroll_line_row = 0;
+            }
+        } else {
+            self.selection_anchor = None;
+            self.matching_bracket = None;
+        }
+        self.synced_changes = buffer.change_count();
+        drop(buffer);
+
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    pub fn get_status(&self) -> DocumentStatus {
+        let buffer = self.buffer();
+        let file_info = buffer.get_file_info();
+
+        DocumentStatus {
+            current_grapheme_index: self.text_location.grapheme_index,
+            current_line_index: self.text_location.line_index,
+            file_name: format!("{file_info}"),
+            file_type: file_info.get_file_type(),
+            is_modified: buffer.is_dirty(),
+            is_read_only: file_info.is_read_only(),
+            encoding: file_info.get_encoding(),
+            line_ending: file_info.get_line_ending(),
+            total_lines: buffer.height(),
+        }
+    }
+
+    pub fn get_file_path(&self) -> Option<PathBuf> {
+        self.buffer()
+            .get_file_info()
+            .get_path()
+            .map(Path::to_path_buf)
+    }
+
+    pub fn is_file_loaded(&self) -> bool {
+        self.buffer().is_file_loaded()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.buffer().is_read_only()
+    }
+
+    pub fn has_mixed_line_endings(&self) -> bool {
+        self.buffer().has_mixed_line_endings()
+    }
+
+    pub fn toggle_line_ending(&mut self) -> LineEnding {
+        self.buffer_mut().toggle_line_ending()
+    }
+
+    // SECTION: search
+
+    pub fn enter_search(&mut self) {
+        self.clear_selection();
+        self.search_info = Some(SearchInfo {
+            current_idx: None,
+            prev_location: self.text_location,
+            prev_scroll_offset: self.scroll_offset,
+            prev_scroll_line_row: self.scroll_line_row,
+            result: None,
+        });
+    }
+
+    pub fn exit_search(&mut self) {
+        self.search_info = None;
+        self.set_needs_redraw(true);
+    }
+
+    pub fn dismiss_search(&mut self) {
+        if let Some(search_info) = &self.search_info {
+            self.text_location = search_info.prev_location;
+            self.scroll_offset = search_info.prev_scroll_offset;
+            self.scroll_line_row = search_info.prev_scroll_line_row;
+        }
+
+        self.exit_search();
+    }
+
+    pub fn search(&mut self, pattern: &Regex) {
+        let result = self.buffer_mut().search(pattern);
+        self.set_search_result(result);
+    }
+
+    // Drops the matches of the last search, e.g. when the query was cleared or isn't a valid pattern
+    pub fn clear_search_result(&mut self) {
+        self.set_search_result(None);
+    }
+
+    // Goes to the first match at or after where the search started. Without any match,
+    // the caret goes back to where it was before searching.
+    fn set_search_result(&mut self, result: Option<Vec<Range<Location>>>) {
+        let Some(search_info) = &mut self.search_info else {
+            return;
+        };
+
+        search_info.current_idx = result.as_ref().map(|result| {
+            result
+                .iter()
+                .position(|range| range.start >= search_info.prev_location)
+                .unwrap_or(0)
+        });
+        search_info.result = result;
+
+        if search_info.result.is_none() {
+            self.text_location = search_info.prev_location;
+            self.scroll_offset = search_info.prev_scroll_offset;
+            self.scroll_line_row = search_info.prev_scroll_line_row;
+        }
+        self.move_to_current_match();
+    }
+
+    // Which match the caret is at, counting from 1, and how many there are
+    pub fn search_position(&self) -> Option<(usize, usize)> {
+        self.search_info.as_ref().and_then(SearchInfo::position)
+    }
+
+    // Returns true if it wrapped around from the last match to the first one
+    pub fn next_search_result(&mut self) -> bool {
+        let mut wrapped = false;
+
+        if let Some(search_info) = &mut self.search_info {
+            if let (Some(result), Some(current_idx)) =
+                (&search_info.result, search_info.current_idx)
+            {
+                let next_idx = current_idx.saturating_add(1);
+                wrapped = next_idx >= result.len();
+                search_info.current_idx = Some(next_idx % result.len());
+                self.move_to_current_match();
+            }
+        }
+
+        wrapped
+    }
+
+    // Returns true if it wrapped around from the first match to the last one
+    pub fn prev_search_result(&mut self) -> bool {
+        let mut wrapped = false;
+
+        if let Some(search_info) = &mut self.search_info {
+            if let (Some(result), Some(current_idx)) =
+                (&search_info.result, search_info.current_idx)
+            {
+                wrapped = current_idx == 0;
+                let len = result.len();
+                search_info.current_idx =
+                    Some(current_idx.saturating_add(len).saturating_sub(1) % len);
+                self.move_to_current_match();
+            }
+        }
+
+        wrapped
+    }
+
+    fn move_to_current_match(&mut self) {
+        if let Some(current_match) = self
+            .search_info
+            .as_ref()
+            .and_then(SearchInfo::current_match)
+        {
+            self.text_location = current_match.start;
+        }
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    // END SECTION
+
+    // SECTION: replace
+
+    pub fn search_result_count(&self) -> usize {
+        self.search_info
+            .as_ref()
+            .and_then(|search_info| search_info.result.as_ref())
+            .map_or(0, Vec::len)
+    }
+
+    // Starts stepping through the search results, from the current one.
+    // All replacements until `end_replace` are undone in a single step.
+    pub fn begin_replace(&mut self) {
+        self.buffer_mut().begin_change_group();
+    }
+
+    pub fn end_replace(&mut self) {
+        self.buffer_mut().end_change_group();
+        self.exit_search();
+    }
+
+    // Replaces the current match and moves on to the next one. Returns false if the match was skipped
+    // instead, because the text changed since and the pattern doesn't match there anymore.
+    pub fn replace_current_match(&mut self, pattern: &Regex, replacement: &str) -> bool {
+        let Some(current_match) = self
+            .search_info
+            .as_ref()
+            .and_then(SearchInfo::current_match)
+        else {
+            return false;
+        };
+
+        let end = self
+            .buffer_mut()
+            .replace_match(current_match.clone(), pattern, replacement);
+        let Some(end) = end else {
+            self.next_search_result();
+            return false;
+        };
+
+        if let Some(search_info) = &mut self.search_info {
+            search_info.remove_current_match(current_match.end, end);
+        }
+        self.text_location = end;
+        self.move_to_current_match();
+        true
+    }
+
+    // END SECTION
+
+    pub fn handle_edit_command(&mut self, command: Edit) {
+        if matches!(command, Edit::Insert('\t')) && self.settings.indent == Indent::Spaces {
+            let spaces = self.spaces_to_tab_stop();
+            self.insert_text(&spaces);
+            return;
+        }
+
+        if let Some(selection) = self.selection() {
+            // Typing or deleting while text is selected replaces the selection
+            match command {
+                Edit::Insert(character) => {
+                    self.replace_selection(selection, &character.to_string());
+                    return;
+                }
+                Edit::InsertNewline => {
+                    self.replace_selection(selection, "\n");
+                    return;
+                }
+                Edit::Delete
+                | Edit::DeleteBackward
+                | Edit::DeleteWord
+                | Edit::DeleteWordBackward => {
+                    self.replace_selection(selection, "");
+                    return;
+                }
+                Edit::Undo | Edit::Redo => {}
+            }
+        }
+
+        self.clear_selection();
+
+        match command {
+            Edit::Insert(character) => self.insert_char(character),
+            Edit::DeleteBackward => self.delete_char_backward(),
+            Edit::Delete => self.delete_char(),
+            Edit::DeleteWordBackward => self.delete_word_backward(),
+            Edit::DeleteWord => self.delete_word(),
+            Edit::InsertNewline => self.insert_new_line(),
+            Edit::Undo => self.undo(),
+            Edit::Redo => self.redo(),
+        }
+    }
+
+    pub fn handle_move_command(&mut self, command: Move, selection_mode: SelectionMode) {
+        match selection_mode {
+            SelectionMode::Extend => {
+                if self.selection_anchor.is_none() {
+                    self.selection_anchor = Some(self.text_location);
+                }
+                self.set_needs_redraw(true);
+            }
+            SelectionMode::Collapse => self.clear_selection(),
+        }
+
+        let prev_location = self.text_location;
+        let Size { height, .. } = self.size;
+        match command {
+            Move::Up => self.move_up(1),
+            Move::Down => self.move_down(1),
+            Move::Left => self.move_left(),
+            Move::Right => self.move_right(),
+            Move::WordLeft => self.move_word_left(),
+            Move::WordRight => self.move_word_right(),
+            Move::StartOfFile => self.jump_to(Location::default()),
+            Move::EndOfFile => self.jump_to(self.location_end_of_file()),
+            Move::PreviousParagraph => self.jump_to(self.location_previous_paragraph()),
+            Move::NextParagraph => self.jump_to(self.location_next_paragraph()),
+            Move::MatchingBracket => {
+                if let Some(location) = self.matching_bracket() {
+                    self.jump_to(location);
+                }
+            }
+            Move::PageUp => self.move_up(height.saturating_sub(1)),
+            Move::PageDown => self.move_down(height.saturating_sub(1)),
+            Move::StartOfLine => self.move_to_start_of_line(),
+            Move::EndOfLine => self.move_to_end_of_line(),
+        }
+
+        // the gutter marks the caret's line, and the partner of a bracket the caret is on or was on is highlighted
+        let line_changed = prev_location.line_index != self.text_location.line_index;
+        if (self.gutter.line_numbers != LineNumbers::Off && line_changed)
+            || self.buffer().is_bracket_at(prev_location)
+            || self.buffer().is_bracket_at(self.text_location)
+        {
+            self.set_needs_redraw(true);
+        }
+
+        self.scroll_text_location_into_view();
+    }
+
+    fn delete_char_backward(&mut self) {
+        if self.text_location.line_index != 0 || self.text_location.grapheme_index != 0 {
+            let at = self.text_location;
+            self.handle_move_command(Move::Left, SelectionMode::Collapse);
+            self.buffer_mut().delete_char_backward(at);
+            self.set_needs_redraw(true);
+        }
+    }
+
+    fn delete_char(&mut self) {
+        self.buffer_mut().delete_char(self.text_location);
+        self.set_needs_redraw(true);
+    }
+
+    fn delete_word_backward(&mut self) {
+        let end = self.text_location;
+        let start = self.location_word_left(end);
+        if start != end {
+            self.buffer_mut().delete_range(start..end);
+            self.move_to(start);
+        }
+    }
+
+    fn delete_word(&mut self) {
+        let start = self.text_location;
+        let end = self.location_word_right(start);
+        if start != end {
+            self.buffer_mut().delete_range(start..end);
+            self.set_needs_redraw(true);
+        }
+    }
+
+    fn insert_char(&mut self, character: char) {
+        let old_len = self.buffer().grapheme_count(self.text_location.line_index);
+
+        self.buffer_mut().insert_char(character, self.text_location);
+
+        let new_len = self.buffer().grapheme_count(self.text_location.line_index);
+
+        let grapheme_delta = new_len.saturating_sub(old_len);
+
+        if grapheme_delta > 0 {
+            self.handle_move_command(Move::Right, SelectionMode::Collapse);
+        }
+
+        self.set_needs_redraw(true);
+    }
+
+    // Indenting with spaces fills up to the next tab stop, from where the selection starts if there is one
+    fn spaces_to_tab_stop(&self) -> String {
+        let location = self
+            .selection()
+            .map_or(self.text_location, |selection| selection.start);
+        let col = self
+            .buffer()
+            .width_until(location.line_index, location.grapheme_index);
+        let tab_width = self.settings.tab_width.max(1);
+
+        " ".repeat(tab_width.saturating_sub(col % tab_width))
+    }
+
+    fn insert_new_line(&mut self) {
+        self.buffer_mut().insert_new_line(self.text_location);
+        self.handle_move_command(Move::Right, SelectionMode::Collapse);
+        self.set_needs_redraw(true);
+    }
+
+    // SECTION: selection
+
+    // Returns the selected range, ordered from its start to its end
+    fn selection(&self) -> Option<Range<Location>> {
+        let anchor = self.selection_anchor?;
+
+        match anchor.cmp(&self.text_location) {
+            Ordering::Less => Some(anchor..self.text_location),
+            Ordering::Greater => Some(self.text_location..anchor),
+            Ordering::Equal => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection()
+            .map(|selection| self.buffer().get_text(selection))
+    }
+
+    // Removes the selected text from the buffer and returns it
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let selection = self.selection()?;
+        let text = self.buffer().get_text(selection.clone());
+
+        self.replace_selection(selection, "");
+
+        Some(text)
+    }
+
+    // Inserts the text at the caret, replacing the selection if there is one
+    pub fn insert_text(&mut self, text: &str) {
+        if let Some(selection) = self.selection() {
+            self.replace_selection(selection, text);
+        } else {
+            let location = self.buffer_mut().insert_text(text, self.text_location);
+            self.move_to(location);
+        }
+    }
+
+    fn replace_selection(&mut self, selection: Range<Location>, text: &str) {
+        self.selection_anchor = None;
+
+        let location = self.buffer_mut().replace(selection, text);
+        self.move_to(location);
+    }
+
+    pub fn clear_selection(&mut self) {
+        if self.selection_anchor.take().is_some() {
+            self.set_needs_redraw(true);
+        }
+    }
+
+    // END SECTION
+
+    fn undo(&mut self) {
+        let location = self.buffer_mut().undo();
+        if let Some(location) = location {
+            self.move_to(location);
+        }
+    }
+
+    fn redo(&mut self) {
+        let location = self.buffer_mut().redo();
+        if let Some(location) = location {
+            self.move_to(location);
+        }
+    }
+
+    fn render_line(&self, at: Position, line: &str) -> Result<(), Error> {
+        Terminal::print_row_in(at, self.size.width, line)
+    }
+
+    fn build_welcome_message(width: usize) -> String {
+        if width == 0 {
+            return String::new();
+        }
+
+        let welcome_message: String = format!("{NAME} - version {VERSION}");
+        let len: usize = welcome_message.len();
+
+        let remaining_width: usize = width.saturating_sub(1);
+
+        if remaining_width <= len {
+            return "~".to_string();
+        }
+
+        format!("{:<1}{:^remaining_width$}", "~", welcome_message)
+    }
+
+    // Rows kept on screen above and below the caret, as far as the view is high enough for them
+    #[allow(clippy::integer_division)]
+    fn scroll_margin(&self) -> usize {
+        min(
+            self.settings.scroll_margin,
+            self.size.height.saturating_sub(1) / 2,
+        )
+    }
+
+    fn scroll_vertically(&mut self, to: RowIdx) {
+        let Size { height, .. } = self.size;
+        let margin = self.scroll_margin();
+        // there is nothing to keep in view past the end of the document
+        let bottom = min(to.saturating_add(margin), self.buffer().height());
+        let offset_changed = if to.saturating_sub(margin) < self.scroll_offset.row {
+            self.scroll_offset.row = to.saturating_sub(margin);
+            true
+        } else if bottom >= self.scroll_offset.row.saturating_add(height) {
+            self.scroll_offset.row = bottom.saturating_sub(height).saturating_add(1);
+            true
+        } else {
+            false
+        };
+
+        if offset_changed {
+            self.set_needs_redraw(true);
+        }
+
+        self.needs_redraw = offset_changed || self.needs_redraw;
+    }
+
+    // `to` is a column including the gutter, which stays put while the text scrolls
+    fn scroll_horizontally(&mut self, to: ColIdx) {
+        let to = to.saturating_sub(self.gutter_width());
+        let width = self.text_width();
+        let offset_changed = if to < self.scroll_offset.col {
+            self.scroll_offset.col = to;
+            true
+        } else if to >= self.scroll_offset.col.saturating_add(width) {
+            self.scroll_offset.col = to.saturating_sub(width).saturating_add(1);
+            true
+        } else {
+            false
+        };
+
+        if offset_changed {
+            self.set_needs_redraw(true);
+        }
+        self.needs_redraw = self.needs_redraw || offset_changed;
+    }
+
+    fn scroll_text_location_into_view(&mut self) {
+        if self.soft_wrap {
+            self.scroll_wrapped_rows();
+            return;
+        }
+
+        let Position { row, col } = self.text_location_to_position();
+
+        self.scroll_vertically(row);
+        self.scroll_horizontally(col);
+    }
+
+    // Scrolls so the caret's row is in the middle of the view, or as close to it as the end of
+    // the document allows
+    fn center_text_location(&mut self) {
+        let height = self.size.height;
+        let caret = self.row_of(self.text_location);
+
+        let mut rows_below: usize = 0;
+        let mut row = caret;
+        while rows_below < height.saturating_sub(1) {
+            match self.next_row(row) {
+                Some(next) => row = next,
+                None => break,
+            }
+            rows_below = rows_below.saturating_add(1);
+        }
+
+        #[allow(clippy::integer_division)]
+        let rows_above = max(
+            height / 2,
+            height.saturating_sub(1).saturating_sub(rows_below),
+        );
+
+        let mut top = caret;
+        for _ in 0..rows_above {
+            match self.prev_row(top) {
+                Some(prev) => top = prev,
+                None => break,
+            }
+        }
+
+        (self.scroll_offset.row, self.scroll_line_row) = top;
+        self.set_needs_redraw(true);
+        self.scroll_text_location_into_view();
+    }
+
+    pub fn caret_position(&self) -> Position {
+        if self.soft_wrap {
+            return self.wrapped_caret_position();
+        }
+
+        self.text_location_to_position()
+            .saturating_sub(self.scroll_offset)
+    }
+
+    fn text_location_to_position(&self) -> Position {
+        let row = self.text_location.line_index;
+        let col = self
+            .buffer()
+            .width_until(row, self.text_location.grapheme_index)
+            .saturating_add(self.gutter_width());
+        Position { row, col }
+    }
+
+    fn move_up(&mut self, step: usize) {
+        if self.soft_wrap {
+            self.move_by_rows(step, false);
+            return;
+        }
+
+        self.text_location.line_index = self.text_location.line_index.saturating_sub(step);
+        let grapheme_count = self.buffer().grapheme_count(self.text_location.line_index);
+        self.text_location.grapheme_index =
+            min(grapheme_count, self.prev_text_location.grapheme_index);
+        self.snap_to_valid_grapheme();
+    }
+
+    fn move_down(&mut self, step: usize) {
+        if self.soft_wrap {
+            self.move_by_rows(step, true);
+            return;
+        }
+
+        self.text_location.line_index = self.text_location.line_index.saturating_add(step);
+        let grapheme_count = self
+            .buffer()
+            .grapheme_count(min(self.text_location.line_index, self.buffer().len() - 1));
+        self.text_location.grapheme_index =
+            min(grapheme_count, self.prev_text_location.grapheme_index);
+        self.snap_to_valid_grapheme();
+        self.snap_to_valid_line();
+    }
+
+    #[allow(clippy::arithmetic_side_effects)]
+    fn move_right(&mut self) {
+        let grapheme_count = self.buffer().grapheme_count(self.text_location.line_index);
+
+        if self.text_location.grapheme_index < grapheme_count {
+            self.text_location.grapheme_index += 1;
+        } else {
+            self.move_to_start_of_line();
+            self.move_down(1);
+        }
+        self.prev_text_location = self.text_location;
+    }
+
+    #[allow(clippy::arithmetic_side_effects)]
+    fn move_left(&mut self) {
+        if self.text_location.grapheme_index > 0 {
+            self.text_location.grapheme_index -= 1;
+        } else if self.text_location.line_index > 0 {
+            self.move_up(1);
+            self.move_to_end_of_line();
+        }
+        self.prev_text_location = self.text_location;
+    }
+
+    // Moves the caret to the location and scrolls it to the middle of the view
+    pub fn go_to(&mut self, location: Location) {
+        self.clear_selection();
+        self.move_to(location);
+        self.center_text_location();
+    }
+
+    fn jump_to(&mut self, location: Location) {
+        self.text_location = location;
+        self.prev_text_location = location;
+    }
+
+    fn location_end_of_file(&self) -> Location {
+        let buffer = self.buffer();
+        let line_index = buffer.height().saturating_sub(1);
+        Location {
+            line_index,
+            grapheme_index: buffer.grapheme_count(line_index),
+        }
+    }
+
+    // The blank line before the paragraph the caret is in, or before the one above if the caret is
+    // on a blank line already. Without one, that's the start of the document.
+    fn location_previous_paragraph(&self) -> Location {
+        let buffer = self.buffer();
+        let line_index = (0..self.text_location.line_index)
+            .rev()
+            .find(|&idx| buffer.is_blank_line(idx) && !buffer.is_blank_line(idx.saturating_add(1)))
+            .unwrap_or(0);
+        Location {
+            line_index,
+            grapheme_index: 0,
+        }
+    }
+
+    // The blank line after the paragraph the caret is in, or after the one below if the caret is
+    // on a blank line already. Without one, that's the end of the document.
+    fn location_next_paragraph(&self) -> Location {
+        let buffer = self.buffer();
+        let next_blank_line = (self.text_location.line_index.saturating_add(1)..buffer.height())
+            .find(|&idx| buffer.is_blank_line(idx) && !buffer.is_blank_line(idx.saturating_sub(1)));
+        drop(buffer);
+
+        next_blank_line.map_or_else(
+            || self.location_end_of_file(),
+            |line_index| Location {
+                line_index,
+                grapheme_index: 0,
+            },
+        )
+    }
+
+    fn move_word_left(&mut self) {
+        self.text_location = self.location_word_left(self.text_location);
+        self.prev_text_location = self.text_location;
+    }
+
+    fn move_word_right(&mut self) {
+        self.text_location = self.location_word_right(self.text_location);
+        self.prev_text_location = self.text_location;
+    }
+
+    // Where moving a word to the left ends up. The line break before a line counts as a word of its own.
+    fn location_word_left(&self, location: Location) -> Location {
+        let Location {
+            line_index,
+            grapheme_index,
+        } = location;
+
+        if grapheme_index == 0 {
+            if line_index == 0 {
+                return location;
+            }
+            let line_index = line_index.saturating_sub(1);
+            return Location {
+                line_index,
+                grapheme_index: self.buffer().grapheme_count(line_index),
+            };
+        }
+
+        Location {
+            line_index,
+            grapheme_index: self.buffer().word_start_before(line_index, grapheme_index),
+        }
+    }
+
+    // Where moving a word to the right ends up. The line break after a line counts as a word of its own.
+    fn location_word_right(&self, location: Location) -> Location {
+        let Location {
+            line_index,
+            grapheme_index,
+        } = location;
+        let buffer = self.buffer();
+
+        if grapheme_index >= buffer.grapheme_count(line_index) {
+            if line_index >= buffer.height() {
+                return location;
+            }
+            return Location {
+                line_index: line_index.saturating_add(1),
+                grapheme_index: 0,
+            };
+        }
+
+        Location {
+            line_index,
+            grapheme_index: buffer.word_end_after(line_index, grapheme_index),
+        }
+    }
+
+    fn move_to(&mut self, location: Location) {
+        self.text_location = location;
+        self.prev_text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    fn move_to_start_of_line(&mut self) {
+        self.text_location.grapheme_index = 0;
+        self.prev_text_location = self.text_location;
+    }
+
+    fn move_to_end_of_line(&mut self) {
+        let grapheme_count = self.buffer().grapheme_count(self.text_location.line_index);
+        self.text_location.grapheme_index = grapheme_count;
+        self.prev_text_location = self.text_location;
+    }
+
+    // Ensures self.location.grapheme_index points to a valid grapheme index by snapping it to the left most grapheme if appropriate.
+    // Doesn't trigger scrolling.
+    fn snap_to_valid_grapheme(&mut self) {
+        let grapheme_count = self.buffer().grapheme_count(self.text_location.line_index);
+        self.text_location.grapheme_index = min(self.text_location.grapheme_index, grapheme_count);
+    }
+
+    // Ensures self.location.line_index points to a valid line index by snapping it to the bottom most line if appropriate.
+    // Doesn't trigger scrolling.
+    fn snap_to_valid_line(&mut self) {
+        let height = self.buffer().height();
+        self.text_location.line_index = min(self.text_location.line_index, height);
+    }
+
+    // SECTION: soft wrap
+
+    // Turns soft wrap on or off and returns whether it's on now
+    pub fn toggle_soft_wrap(&mut self) -> bool {
+        self.soft_wrap = !self.soft_wrap;
+        self.scroll_offset.col = 0;
+        self.scroll_line_row = 0;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        self.soft_wrap
+    }
+
+    // Where the rows of the line start on screen. Without soft wrap, every line is a single row.
+    fn row_starts(&self, line_idx: LineIdx) -> Vec<GraphemeIdx> {
+        if self.soft_wrap {
+            self.buffer().wrap(line_idx, self.text_width())
+        } else {
+            vec![0]
+        }
+    }
+
+    // The screen row the location is on, as its line and the row within that line
+    fn row_of(&self, location: Location) -> (LineIdx, usize) {
+        let row_starts = self.row_starts(location.line_index);
+        let row = row_starts
+            .partition_point(|&start| start <= location.grapheme_index)
+            .saturating_sub(1);
+        (location.line_index, row)
+    }
+
+    // The row below, None at the end of the document. The line past the last one counts as a row.
+    fn next_row(&self, (line_idx, row): (LineIdx, usize)) -> Option<(LineIdx, usize)> {
+        if row.saturating_add(1) < self.row_starts(line_idx).len() {
+            Some((line_idx, row.saturating_add(1)))
+        } else if line_idx < self.buffer().height() {
+            Some((line_idx.saturating_add(1), 0))
+        } else {
+            None
+        }
+    }
+
+    // The row above, None at the top of the document
+    fn prev_row(&self, (line_idx, row): (LineIdx, usize)) -> Option<(LineIdx, usize)> {
+        if row > 0 {
+            Some((line_idx, row.saturating_sub(1)))
+        } else if line_idx > 0 {
+            let line_idx = line_idx.saturating_sub(1);
+            Some((line_idx, self.row_starts(line_idx).len().saturating_sub(1)))
+        } else {
+            None
+        }
+    }
+
+    // The graphemes shown on the row. Only the last row of a line includes the location past its end.
+    fn row_range(&self, (line_idx, row): (LineIdx, usize)) -> Range<GraphemeIdx> {
+        let row_starts = self.row_starts(line_idx);
+        let start = row_starts.get(row).copied().unwrap_or(0);
+        let end = row_starts
+            .get(row.saturating_add(1))
+            .copied()
+            .unwrap_or_else(|| self.buffer().grapheme_count(line_idx));
+        start..end
+    }
+
+    // How far the location is from the start of its row, in columns
+    fn col_in_row(&self, location: Location) -> ColIdx {
+        let row_start = self.row_range(self.row_of(location)).start;
+        let buffer = self.buffer();
+        buffer
+            .width_until(location.line_index, location.grapheme_index)
+            .saturating_sub(buffer.width_until(location.line_index, row_start))
+    }
+
+    // Moves by screen rows rather than lines, keeping to the column the caret was last moved to sideways
+    fn move_by_rows(&mut self, step: usize, down: bool) {
+        let col = self.col_in_row(self.prev_text_location);
+        let mut row = self.row_of(self.text_location);
+
+        for _ in 0..step {
+            let next = if down {
+                self.next_row(row)
+            } else {
+                self.prev_row(row)
+            };
+            match next {
+                Some(next) => row = next,
+                None => break,
+            }
+        }
+
+        let range = self.row_range(row);
+        let is_last_row = row.1.saturating_add(1) >= self.row_starts(row.0).len();
+        let last = if is_last_row {
+            range.end
+        } else {
+            range.end.saturating_sub(1)
+        };
+
+        let grapheme_index = self.buffer().grapheme_at_col(row.0, range.start, last, col);
+        self.text_location = Location {
+            line_index: row.0,
+            grapheme_index,
+        };
+    }
+
+    // Scrolls just enough to bring the caret's row on screen
+    fn scroll_wrapped_rows(&mut self) {
+        let height = self.size.height;
+        let caret = self.row_of(self.text_location);
+
+        // the top line may have lost rows since it was last wrapped
+        let top_line = self.scroll_offset.row;
+        let top = (
+            top_line,
+            min(
+                self.scroll_line_row,
+                self.row_starts(top_line).len().saturating_sub(1),
+            ),
+        );
+
+        let margin = self.scroll_margin();
+        let above = self.step_rows(caret, margin, false);
+        let below = self.step_rows(caret, margin, true);
+
+        let new_top = if above < top {
+            above
+        } else {
+            let mut row = top;
+            let mut is_visible = false;
+            for _ in 0..height {
+                if row == below {
+                    is_visible = true;
+                    break;
+                }
+                match self.next_row(row) {
+                    Some(next) => row = next,
+                    None => break,
+                }
+            }
+
+            if is_visible {
+                top
+            } else {
+                self.step_rows(below, height.saturating_sub(1), false)
+            }
+        };
+
+        if new_top != (self.scroll_offset.row, self.scroll_line_row) || self.scroll_offset.col > 0 {
+            (self.scroll_offset.row, self.scroll_line_row) = new_top;
+            self.scroll_offset.col = 0;
+            self.set_needs_redraw(true);
+        }
+    }
+
+    // The row `count` rows down or up from the given one, or the last or first row if there aren't that many
+    fn step_rows(&self, mut row: (LineIdx, usize), count: usize, down: bool) -> (LineIdx, usize) {
+        for _ in 0..count {
+            let next = if down {
+                self.next_row(row)
+            } else {
+                self.prev_row(row)
+            };
+            match next {
+                Some(next) => row = next,
+                None => break,
+            }
+        }
+        row
+    }
+
+    fn wrapped_caret_position(&self) -> Position {
+        let caret = self.row_of(self.text_location);
+        let mut row = (self.scroll_offset.row, self.scroll_line_row);
+        let mut rows_above: RowIdx = 0;
+
+        while row < caret && rows_above < self.size.height {
+            match self.next_row(row) {
+                Some(next) => row = next,
+                None => break,
+            }
+            rows_above = rows_above.saturating_add(1);
+        }
+
+        Position {
+            row: rows_above,
+            col: self
+                .col_in_row(self.text_location)
+                .saturating_add(self.gutter_width()),
+        }
+    }
+
+    // The lines on screen, one entry per row, with the row within the line and the columns of the
+    // line that row shows. Rows past the end of the document are None.
+    fn visible_rows(&self) -> Vec<Option<(LineIdx, usize, Range<ColIdx>)>> {
+        let Size { height, .. } = self.size;
+        let width = self.text_width();
+        let buffer_height = self.buffer().height();
+        let mut rows = Vec::with_capacity(height);
+
+        if !self.soft_wrap {
+            let left = self.scroll_offset.col;
+            for current_row in 0..height {
+                let line_idx = current_row.saturating_add(self.scroll_offset.row);
+                rows.push(
+                    (line_idx < buffer_height)
+                        .then(|| (line_idx, 0, left..left.saturating_add(width))),
+                );
+            }
+            return rows;
+        }
+
+        let mut line_idx = self.scroll_offset.row;
+        let mut first_row = self.scroll_line_row;
+        while rows.len() < height && line_idx < buffer_height {
+            let row_starts = self.row_starts(line_idx);
+            let buffer = self.buffer();
+            let grapheme_count = buffer.grapheme_count(line_idx);
+
+            for (row, &start) in row_starts.iter().enumerate().skip(first_row) {
+                if rows.len() >= height {
+                    break;
+                }
+                let end = row_starts
+                    .get(row.saturating_add(1))
+                    .copied()
+                    .unwrap_or(grapheme_count);
+                let left = buffer.width_until(line_idx, start);
+                let right = buffer.width_until(line_idx, end);
+                rows.push(Some((line_idx, row, left..right)));
+            }
+
+            first_row = 0;
+            line_idx = line_idx.saturating_add(1);
+        }
+        rows.resize(height, None);
+        rows
+    }
+
+    // END SECTION
+
+    // SECTION: gutter
+
+    // Cycles through the ways of numbering lines and returns the one in use now
+    pub fn cycle_line_numbers(&mut self) -> LineNumbers {
+        self.gutter.line_numbers = self.gutter.line_numbers.next();
+        self.scroll_offset.col = 0;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        self.gutter.line_numbers
+    }
+
+    fn gutter_width(&self) -> ColIdx {
+        self.gutter.width(self.buffer().height())
+    }
+
+    // The room left for the text next to the gutter
+    fn text_width(&self) -> ColIdx {
+        self.size.width.saturating_sub(self.gutter_width())
+    }
+
+    // END SECTION
+
+    pub fn load_file(&mut self, file_name: &str) -> Result<(), Error> {
+        let buffer = Buffer::load_file(file_name)?;
+        self.buffer = Rc::new(RefCell::new(buffer));
+        self.synced_changes = 0;
+        self.matching_bracket = None;
+        self.set_needs_redraw(true);
+        Ok(())
+    }
+
+    // SECTION: swap file
+
+    pub fn update_swap(&mut self, is_idle: bool) -> Result<(), Error> {
+        self.buffer_mut().update_swap(is_idle)
+    }
+
+    pub fn remove_swap(&mut self) {
+        self.buffer_mut().remove_swap();
+    }
+
+    pub fn has_leftover_swap(&self) -> bool {
+        self.buffer().has_leftover_swap()
+    }
+
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        self.buffer_mut().recover_swap()?;
+        self.move_to(Location::default());
+        Ok(())
+    }
+
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        self.buffer_mut().discard_swap()
+    }
+
+    pub fn swap_diff(&self) -> Result<String, Error> {
+        self.buffer().swap_diff()
+    }
+
+    // END SECTION
+
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.keep_backup = keep_backup;
+    }
+
+    const fn tab_stops(&self) -> TabStops {
+        TabStops {
+            width: self.settings.tab_width,
+            show: self.settings.show_tabs,
+        }
+    }
+
+    // Takes on the settings for the file type, replacing whatever was toggled before
+    pub fn apply_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        let tab_stops = self.tab_stops();
+        self.buffer_mut().set_tab_stops(tab_stops);
+        self.soft_wrap = settings.soft_wrap;
+        self.gutter.line_numbers = settings.line_numbers;
+        self.scroll_offset.col = 0;
+        self.scroll_line_row = 0;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    // Reads the file from disk again, dropping any unsaved changes. The caret stays where it was, as far as possible.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let Some(file_name) = self
+            .get_file_path()
+            .map(|path| path.to_string_lossy().into_owned())
+        else {
+            return Ok(());
+        };
+
+        // our own swap file would look like a leftover otherwise; should loading fail, it's written again
+        self.buffer_mut().remove_swap();
+        let mut buffer = Buffer::load_file(&file_name)?;
+        buffer.set_tab_stops(self.tab_stops());
+        self.buffer_mut().replace_with(buffer);
+        self.mark_synced();
+        self.matching_bracket = None;
+        self.selection_anchor = None;
+        self.move_to(self.text_location);
+        Ok(())
+    }
+
+    pub fn has_changed_on_disk(&self) -> bool {
+        self.buffer().has_changed_on_disk()
+    }
+
+    pub fn save_file(&mut self) -> Result<(), Error> {
+        let keep_backup = self.keep_backup;
+        self.buffer_mut().save(keep_backup)?;
+        self.set_needs_redraw(true);
+        Ok(())
+    }
+
+    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+        let keep_backup = self.keep_backup;
+        self.buffer_mut().save_as(file_name, keep_backup)?;
+        self.set_needs_redraw(true);
+        Ok(())
+    }
+}
+
+impl UIComponent for View {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.scroll_text_location_into_view();
+    }
+
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        let Size { width, height } = self.size;
+
+        #[allow(clippy::integer_division)]
+        let top_third = height / 3;
+        let scroll_top = self.scroll_offset.row;
+
+        let selected_match = self
+            .search_info
+            .as_ref()
+            .and_then(SearchInfo::current_match);
+
+        let matching_bracket = self.matching_bracket();
+        let mut highlighter = Highlighter::new(
+            self.buffer().get_file_info().get_file_type(),
+            self.search_info.is_some(),
+            selected_match,
+            self.selection(),
+            matching_bracket,
+        );
+
+        self.buffer()
+            .start_highlighting(scroll_top, &mut highlighter);
+
+        for current_row in scroll_top..height.saturating_add(scroll_top) {
+            let search_results = if let Some(search_info) = &self.search_info {
+                if let Some(locations) = &search_info.result {
+                    let res = locations
+                        .iter()
+                        .filter(|range| range.start.line_index == current_row)
+                        .map(|range| range.start.grapheme_index..range.end.grapheme_index)
+                        .collect::<Vec<_>>();
+                    Some(res)
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
+            self.buffer()
+                .highlight(current_row, &search_results, &mut highlighter);
+        }
+
+        let line_count = self.buffer().height();
+        let gutter_width = self.gutter_width();
+        let text_at_col = origin.col.saturating_add(gutter_width);
+
+        for (current_row, visible_row) in self.visible_rows().into_iter().enumerate() {
+            let at = Position {
+                row: origin.row.saturating_add(current_row),
+                col: origin.col,
+            };
+
+            if let Some((line_idx, row, annotated_string)) =
+                visible_row.and_then(|(line_idx, row, cols)| {
+                    self.buffer()
+                        .get_highlighted_substring(line_idx, cols, &highlighter)
+                        .map(|annotated_string| (line_idx, row, annotated_string))
+                })
+            {
+                let gutter = self.gutter.render(
+                    Some(line_idx),
+                    row == 0,
+                    self.text_location.line_index,
+                    line_count,
+                );
+                Terminal::print_annotated_row_in(at, gutter_width, &gutter)?;
+                Terminal::print_annotated_row_in(
+                    Position {
+                        col: text_at_col,
+                        ..at
+                    },
+                    width.saturating_sub(gutter_width),
+                    &annotated_string,
+                )?;
+            } else if current_row == top_third && self.buffer().is_empty() {
+                self.render_line(at, &Self::build_welcome_message(width))?;
+            } else {
+                self.render_line(at, "~")?;
+            }
+        }
+
+        Ok(())
+    }
+}
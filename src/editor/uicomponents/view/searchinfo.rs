@@ -1,13 +1,61 @@
-// use crate::editor::{Line, Position};
-use crate::editor::Line;
-use crate::prelude::*;
-
-use super::Location;
-
-pub struct SearchInfo {
-    pub current_idx: Option<usize>,
-    pub prev_location: Location,
-    pub prev_scroll_offset: Position,
-    pub query: Option<Line>,
-    pub result: Option<Vec<Location>>,
-}
+use crate::prelude::*;
+use std::ops::Range;
+
+use super::Location;
+
+#[derive(Clone)]
+pub struct SearchInfo {
+    pub current_idx: Option<usize>,
+    pub prev_location: Location,
+    pub prev_scroll_offset: Position,
+    pub prev_scroll_line_row: usize,
+    pub result: Option<Vec<Range<Location>>>,
+}
+
+impl SearchInfo {
+    // The match the caret is at
+    pub fn current_match(&self) -> Option<Range<Location>> {
+        let result = self.result.as_ref()?;
+        result.get(self.current_idx?).cloned()
+    }
+
+    // Which match the caret is at, counting from 1, and how many there are
+    pub fn position(&self) -> Option<(usize, usize)> {
+        let result = self.result.as_ref()?;
+        Some((self.current_idx?.saturating_add(1), result.len()))
+    }
+
+    // Drops the current match once it was replaced, and moves the matches after it on the same line
+    // along with the end of the replacement. The match after it becomes the current one.
+    pub fn remove_current_match(&mut self, replaced_end: Location, new_end: Location) {
+        let (Some(result), Some(current_idx)) = (&mut self.result, self.current_idx) else {
+            return;
+        };
+
+        if current_idx < result.len() {
+            result.remove(current_idx);
+        }
+
+        let shift = |location: &mut Location| {
+            location.grapheme_index = location
+                .grapheme_index
+                .saturating_sub(replaced_end.grapheme_index)
+                .saturating_add(new_end.grapheme_index);
+        };
+
+        for range in result.iter_mut().filter(|range| {
+            range.start.line_index == replaced_end.line_index && range.start >= replaced_end
+        }) {
+            shift(&mut range.start);
+            shift(&mut range.end);
+        }
+
+        let len = result.len();
+        if len == 0 {
+            self.result = None;
+            self.current_idx = None;
+        } else {
+            self.current_idx = Some(current_idx % len);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::AtomicFile;
+use ropey::Rope;
+use std::{
+    fs,
+    io::{BufWriter, Error, IntoInnerError},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// While the document keeps changing, the swap file is written at least this often
+const WRITE_INTERVAL: Duration = Duration::from_secs(10);
+
+// The latest unsaved contents of every document, written out by `flush_snapshots` when the editor panics.
+// Ropes share their data, so keeping a snapshot around is cheap.
+static SNAPSHOTS: Mutex<Vec<(PathBuf, Rope)>> = Mutex::new(Vec::new());
+
+// A copy of the unsaved contents of a document, kept next to it as `.<file name>.swp`
+pub struct SwapFile {
+    path: PathBuf,
+    written_revision: Option<usize>, // revision of the document the swap file on disk holds
+    snapshot_revision: Option<usize>,
+    is_owned: bool, // the swap file on disk belongs to this session and is removed along with it
+    last_write: Instant,
+    has_leftover: bool, // a swap file from an earlier session was found, and is kept until recovered or discarded
+}
+
+impl SwapFile {
+    pub fn for_document(document: &Path) -> Self {
+        let file_name = document
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = document.with_file_name(format!(".{file_name}.swp"));
+
+        Self {
+            has_leftover: path.exists(),
+            path,
+            written_revision: None,
+            snapshot_revision: None,
+            is_owned: false,
+            last_write: Instant::now(),
+        }
+    }
+
+    pub const fn has_leftover(&self) -> bool {
+        self.has_leftover
+    }
+
+    pub fn read_leftover(&self) -> Result<String, Error> {
+        fs::read_to_string(&self.path)
+    }
+
+    pub fn discard_leftover(&mut self) -> Result<(), Error> {
+        self.has_leftover = false;
+        fs::remove_file(&self.path)
+    }
+
+    // The recovered changes are part of the document now, so the swap file is taken over
+    pub fn recovered(&mut self) {
+        self.has_leftover = false;
+        self.is_owned = true;
+    }
+
+    // Keeps the swap file up to date with the given revision of the document.
+    // It is written when the editor is idle, or when the last write is too long ago.
+    pub fn update(&mut self, text: &Rope, revision: usize, is_idle: bool) -> Result<(), Error> {
+        if self.has_leftover || self.written_revision == Some(revision) {
+            return Ok(());
+        }
+
+        if self.snapshot_revision != Some(revision) {
+            self.snapshot_revision = Some(revision);
+            remember_snapshot(&self.path, text);
+        }
+
+        if is_idle || self.last_write.elapsed() >= WRITE_INTERVAL {
+            self.last_write = Instant::now();
+            write_swap(&self.path, text)?;
+            self.written_revision = Some(revision);
+            self.is_owned = true;
+        }
+
+        Ok(())
+    }
+
+    // Removes the swap file, unless it is a leftover from an earlier session
+    pub fn remove(&mut self) {
+        if self.snapshot_revision.take().is_some() {
+            forget_snapshot(&self.path);
+        }
+
+        self.written_revision = None;
+        if self.is_owned {
+            self.is_owned = false;
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+// The swap file holds the same contents as the document, so nobody else gets to read it
+fn write_swap(path: &Path, text: &Rope) -> Result<(), Error> {
+    let mut writer = BufWriter::new(AtomicFile::create_private(path)?);
+    text.write_to(&mut writer)?;
+    writer
+        .into_inner()
+        .map_err(IntoInnerError::into_error)?
+        .commit(false)?;
+    Ok(())
+}
+
+fn remember_snapshot(path: &Path, text: &Rope) {
+    if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+        snapshots.retain(|(snapshot_path, _)| snapshot_path != path);
+        snapshots.push((path.to_path_buf(), text.clone()));
+    }
+}
+
+fn forget_snapshot(path: &Path) {
+    if let Ok(mut snapshots) = SNAPSHOTS.lock() {
+        snapshots.retain(|(snapshot_path, _)| snapshot_path != path);
+    }
+}
+
+// Writes the latest contents of every modified document to its swap file.
+// Meant to be called from the panic hook, so it never waits for the lock and ignores errors.
+pub fn flush_snapshots() {
+    if let Ok(snapshots) = SNAPSHOTS.try_lock() {
+        for (path, text) in snapshots.iter() {
+            let _ = write_swap(path, text);
+        }
+    }
+}
@@ -1,19 +1,24 @@
-use editor::Editor;
-
-mod editor;
-mod prelude;
-
-#[warn(
-    clippy::all,
-    clippy::pedantic,
-    clippy::nursery,
-    clippy::cargo,
-    clippy::restriction,
-    clippy::print_stdout,
-    clippy::arithmetic_side_effects,
-    clippy::as_conversions,
-    clippy::integer_division
-)]
-fn main() {
-    Editor::new().unwrap().run();
-}
+use editor::Editor;
+
+mod editor;
+mod prelude;
+
+#[warn(
+    clippy::all,
+    clippy::pedantic,
+    clippy::nursery,
+    clippy::cargo,
+    clippy::restriction,
+    clippy::print_stdout,
+    clippy::arithmetic_side_effects,
+    clippy::as_conversions,
+    clippy::integer_division
+)]
+#[expect(
+    clippy::blanket_clippy_restriction_lints,
+    clippy::unwrap_used,
+    reason = "restriction lints are opted into as a group; startup failure should abort"
+)]
+fn main() {
+    Editor::new().unwrap().run();
+}
@@ -1,7 +1,8 @@
-use super::{GraphemeIdx, LineIdx};
-
-#[derive(Clone, Copy, Debug, Default)]
-pub struct Location {
-    pub grapheme_index: GraphemeIdx,
-    pub line_index: LineIdx,
-}
+use super::{GraphemeIdx, LineIdx};
+
+// Field order matters: locations are ordered by line first, then by grapheme
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub line_index: LineIdx,
+    pub grapheme_index: GraphemeIdx,
+}
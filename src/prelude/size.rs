@@ -1,5 +1,5 @@
-#[derive(Default, Clone, Copy, Eq, PartialEq)]
-pub struct Size {
-    pub width: usize,
-    pub height: usize,
-}
+#[derive(Default, Clone, Copy, Eq, PartialEq)]
+pub struct Size {
+    pub width: usize,
+    pub height: usize,
+}